@@ -1,9 +1,34 @@
-use rand::random;
+use anyhow::{bail, Result};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-use super::opt::OptimizationStrategy;
+use super::{
+    config::DepotReturn,
+    heuristic::{ConstructionHeuristic, EdgeContext},
+    opt::OptimizationStrategy,
+};
 
 use yoos::collections::Matrix;
 
+/// The powers the savings, pheromone and inverse distance of an edge are raised to when weighing
+/// it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Exponents {
+    pub savings: f64,
+    pub pheromone: f64,
+    pub distance: f64,
+}
+
+impl Default for Exponents {
+    fn default() -> Self {
+        Self {
+            savings: 9.0,
+            pheromone: 2.0,
+            distance: 5.0,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Ant {
     path_taken: Vec<usize>,
@@ -11,12 +36,56 @@ pub struct Ant {
     visited: Vec<bool>,
     visited_count: usize,
     num_nodes: usize,
-    capacity: usize,
-    cur_capacity: usize,
+    capacity: f64,
+    cur_capacity: f64,
+    // Always takes the most attractive edge instead of sampling one
+    greedy: bool,
+    // How far the current route may go, including the way back to the depot
+    max_route_distance: Option<f64>,
+    route_distance: f64,
+    depot_return: Option<DepotReturn>,
+    // Scratch space for the weight of each edge out of the current node, kept between moves
+    // and cycles so that choosing the next node doesn't allocate
+    distribution: Vec<Option<f64>>,
+    // Scratch space for the running totals the next node is picked from, likewise kept
+    roulette: Roulette,
+}
+
+/// The nodes an ant may move to and the running total of their weights, kept between moves so
+/// that picking one doesn't allocate.
+#[derive(Clone, Debug, Default)]
+struct Roulette {
+    candidates: Vec<usize>,
+    cumulative: Vec<f64>,
+}
+
+impl Roulette {
+    /// Picks a node at random with probability proportional to its weight, by finding where
+    /// `rand * total` falls in the running total of the weights.
+    ///
+    /// Nodes take their slices of the total in ascending order, so for the same `rand`, ties
+    /// between equally weighted nodes always go to the lower index of those in range.
+    fn pick(&mut self, distribution: &[Option<f64>], rand: f64) -> usize {
+        self.candidates.clear();
+        self.cumulative.clear();
+        let mut total = 0.0;
+        for (i, weight) in distribution.iter().enumerate() {
+            if let Some(weight) = weight {
+                total += weight;
+                self.candidates.push(i);
+                self.cumulative.push(total);
+            }
+        }
+
+        let threshold = rand * total;
+        // Rounding can leave the threshold just past the total, in which case take the last node
+        let chosen = self.cumulative.partition_point(|&c| c < threshold);
+        self.candidates[chosen.min(self.candidates.len() - 1)]
+    }
 }
 
 impl Ant {
-    pub fn new(num_nodes: usize, capacity: usize) -> Self {
+    pub fn new(num_nodes: usize, capacity: f64) -> Self {
         let mut visited = vec![false; num_nodes];
         visited[0] = true;
         Self {
@@ -27,94 +96,232 @@ impl Ant {
             num_nodes,
             capacity,
             cur_capacity: capacity,
+            greedy: false,
+            max_route_distance: None,
+            route_distance: 0.0,
+            depot_return: None,
+            distribution: Vec::with_capacity(num_nodes),
+            roulette: Roulette {
+                candidates: Vec::with_capacity(num_nodes),
+                cumulative: Vec::with_capacity(num_nodes),
+            },
+        }
+    }
+
+    /// An ant that always moves along the most attractive edge rather than choosing at random,
+    /// so it builds the same tour given the same pheromones.
+    pub fn new_greedy(num_nodes: usize, capacity: f64) -> Self {
+        Self {
+            greedy: true,
+            ..Self::new(num_nodes, capacity)
         }
     }
 
+    /// Limits each route to `max_route_distance`, counting the way back to the depot, by only
+    /// moving to customers it can still return from in time.
+    pub fn with_max_route_distance(mut self, max_route_distance: Option<f64>) -> Self {
+        self.max_route_distance = max_route_distance;
+        self
+    }
+
+    /// Lets the ant head back to the depot early once its vehicle is nearly full, as
+    /// `depot_return` describes. Greedy ants never do.
+    pub fn with_depot_return(mut self, depot_return: Option<DepotReturn>) -> Self {
+        self.depot_return = depot_return;
+        self
+    }
+
+    /// Puts the ant back at the depot with an empty path, ready for another cycle, keeping its
+    /// settings and the memory it has already allocated.
+    pub fn reset(&mut self) {
+        self.path_taken.clear();
+        self.path_taken.push(0);
+        self.path_cost = 0.0;
+        self.visited.fill(false);
+        self.visited[0] = true;
+        self.visited_count = 1;
+        self.cur_capacity = self.capacity;
+        self.route_distance = 0.0;
+    }
+
     pub fn done(&self) -> bool {
         self.num_nodes == self.visited_count
     }
 
-    pub fn move_to_next(
+    /// Moves along one edge, to a customer or back to the depot, drawing any random choice from
+    /// `rng`. Fails if the ant is at the depot with an empty vehicle and still can't fit or
+    /// reach any customer it has left to visit, since otherwise it would never finish.
+    pub fn move_to_next<R: Rng + ?Sized>(
         &mut self,
         adjacency_matrix: &Matrix,
-        pheromones: &Matrix,
-        nodes: &[usize],
-    ) {
+        attractiveness: &Matrix,
+        nodes: &[f64],
+        rng: &mut R,
+    ) -> Result<()> {
         let cur_node = self.cur_node();
 
         if cur_node == 0 {
             self.cur_capacity = self.capacity;
+            self.route_distance = 0.0;
         }
 
-        let next_node = self.find_next_node(adjacency_matrix, pheromones, nodes);
+        let mut distribution = std::mem::take(&mut self.distribution);
+        let mut roulette = std::mem::take(&mut self.roulette);
+        let next_node = self.find_next_node(
+            adjacency_matrix,
+            attractiveness,
+            nodes,
+            &mut distribution,
+            &mut roulette,
+            rng,
+        );
+        self.distribution = distribution;
+        self.roulette = roulette;
+
+        if cur_node == 0 && next_node == 0 {
+            if let Some(node) = (0..self.num_nodes).find(|&i| !self.visited[i]) {
+                if nodes[node] > self.capacity {
+                    bail!(
+                        "No vehicle can serve node {}, its demand of {} exceeds the capacity of {}",
+                        node + 1,
+                        nodes[node],
+                        self.capacity
+                    );
+                }
+                bail!(
+                    "No vehicle can serve node {}, going there and back is longer than the \
+                     maximum route distance",
+                    node + 1
+                );
+            }
+        }
 
         self.path_cost += adjacency_matrix[cur_node][next_node];
+        self.route_distance += adjacency_matrix[cur_node][next_node];
 
         self.cur_capacity -= *nodes.get(next_node).unwrap();
 
         self.visit(next_node);
+        Ok(())
     }
 
     pub fn cur_node(&self) -> usize {
         *self.path_taken.last().unwrap()
     }
 
-    fn find_next_node(
+    fn find_next_node<R: Rng + ?Sized>(
         &self,
         adjacency_matrix: &Matrix,
-        pheromones: &Matrix,
-        nodes: &[usize],
+        attractiveness: &Matrix,
+        nodes: &[f64],
+        distribution_vec: &mut Vec<Option<f64>>,
+        roulette: &mut Roulette,
+        rng: &mut R,
     ) -> usize {
-        let mut distribution_vec: Vec<Option<f64>> = vec![None; adjacency_matrix.size()];
-        let cur_node = self.cur_node();
-        let mut total_edge_weight: f64 = 0.0;
+        self.edge_weights(attractiveness, nodes, distribution_vec);
 
-        for (i, d) in distribution_vec.iter_mut().enumerate() {
-            if !self.visited[i] {
-                let distance_to_depot = adjacency_matrix[cur_node][0];
-                let distance_from_depot = adjacency_matrix[0][i];
-                let distance_to_next = adjacency_matrix[cur_node][i];
-                let savings = distance_to_depot + distance_from_depot - distance_to_next;
-
-                let pheromone = pheromones[cur_node][i];
-                let edge_weight = Self::calc_edge_weight(savings, pheromone, distance_to_next);
-                total_edge_weight += edge_weight;
-                *d = Some(edge_weight);
+        // Customers too far to get back to the depot from within the route's distance
+        if let Some(max_route_distance) = self.max_route_distance {
+            let cur_node = self.cur_node();
+            for (i, weight) in distribution_vec.iter_mut().enumerate() {
+                let round_trip = adjacency_matrix[cur_node][i] + adjacency_matrix[i][0];
+                if self.route_distance + round_trip > max_route_distance {
+                    *weight = None;
+                }
             }
         }
 
-        let mut next_node =
-            Self::get_next_node_by_probability(&distribution_vec, total_edge_weight);
+        // Nothing left fits in the vehicle, head back to the depot
+        if distribution_vec.iter().all(Option::is_none) {
+            return 0;
+        }
+
+        if let Some(depot_return) = self.depot_return {
+            let fill = 1.0 - self.cur_capacity / self.capacity;
+            if !self.greedy
+                && self.cur_node() != 0
+                && fill >= depot_return.fill
+                && rng.gen::<f64>() < depot_return.probability
+            {
+                return 0;
+            }
+        }
 
-        if self.cur_capacity < *nodes.get(next_node).unwrap() {
-            next_node = 0;
+        if self.greedy {
+            // The heaviest edge, ties going to the lowest node
+            return distribution_vec
+                .iter()
+                .enumerate()
+                .filter_map(|(i, weight)| weight.map(|w| (i, w)))
+                .max_by(|(i, a), (j, b)| a.total_cmp(b).then(j.cmp(i)))
+                .map(|(i, _)| i)
+                .unwrap();
         }
 
-        next_node
+        roulette.pick(distribution_vec, rng.gen())
     }
 
-    fn get_next_node_by_probability(distribution: &[Option<f64>], total_edge_weight: f64) -> usize {
-        let rand: f64 = random();
-        let ratio: f64 = 1.0f64 / total_edge_weight;
-        let mut temp_dist: f64 = 0.0;
-        for (i, d) in distribution.iter().enumerate() {
-            if let Some(d_value) = d {
-                temp_dist += d_value;
-                if rand / ratio <= temp_dist {
-                    return i;
-                }
-            };
+    /// The log of how attractive each edge is under `heuristic`, given the pheromones laid so
+    /// far. Pheromones only change between cycles, so this is computed once per cycle and shared
+    /// by every ant.
+    pub fn attractiveness(
+        adjacency_matrix: &Matrix,
+        pheromones: &Matrix,
+        exponents: Exponents,
+        heuristic: &dyn ConstructionHeuristic,
+    ) -> Matrix {
+        let n = adjacency_matrix.size();
+        let mut attractiveness = Matrix::new(n);
+        for from in 0..n {
+            for to in 0..n {
+                attractiveness[from][to] = heuristic.edge_weight(&EdgeContext {
+                    from,
+                    to,
+                    distance: adjacency_matrix[from][to],
+                    distance_to_depot: adjacency_matrix[from][0],
+                    distance_from_depot: adjacency_matrix[0][to],
+                    pheromone: pheromones[from][to],
+                    exponents,
+                });
+            }
         }
-
-        // TODO: Think about this
-        unreachable!()
+        attractiveness
     }
 
-    fn calc_edge_weight(savings: f64, pheromone: f64, distance_to_next: f64) -> f64 {
-        let e = savings.powi(9);
-        let p = pheromone.powi(2);
-        let d = (1.0f64 / distance_to_next).powi(5);
-        e * p * d
+    /// Fills `distribution_vec` with the relative weight of moving to each unvisited node from
+    /// the current node, `None` for visited ones and ones whose demand doesn't fit in the
+    /// remaining capacity. Weights are computed in log space and scaled so that the largest is
+    /// 1.0, which keeps them finite however large the savings or small the distances get.
+    fn edge_weights(
+        &self,
+        attractiveness: &Matrix,
+        nodes: &[f64],
+        distribution_vec: &mut Vec<Option<f64>>,
+    ) {
+        distribution_vec.clear();
+        distribution_vec.resize(attractiveness.size(), None);
+        let cur_node = self.cur_node();
+        let mut max_log_weight = f64::NEG_INFINITY;
+
+        for (i, d) in distribution_vec.iter_mut().enumerate() {
+            // Zero demand customers always fit, even in a full vehicle, so they never force a
+            // return to the depot
+            if !self.visited[i] && nodes[i] <= self.cur_capacity {
+                let log_weight = attractiveness[cur_node][i];
+                max_log_weight = max_log_weight.max(log_weight);
+                *d = Some(log_weight);
+            }
+        }
+
+        for weight in distribution_vec.iter_mut().flatten() {
+            // If every weight is zero (e.g. no savings leaving the depot), keep them that way
+            // rather than computing -inf - -inf
+            *weight = if max_log_weight == f64::NEG_INFINITY {
+                0.0
+            } else {
+                (*weight - max_log_weight).exp()
+            };
+        }
     }
 
     fn visit(&mut self, idx: usize) {
@@ -142,10 +349,413 @@ impl Ant {
     pub fn optimize_path<S: OptimizationStrategy>(
         &mut self,
         adjacency_matrix: &Matrix,
-        strategy: S,
+        strategy: &S,
     ) {
         let (path, cost) = strategy.optimize(self.path_taken(), adjacency_matrix);
         self.path_taken = path;
         self.path_cost = cost
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::aco::heuristic::SavingsHeuristic;
+
+    /// How attractive each edge is with uniform pheromones and the default exponents.
+    fn uniform_attractiveness(adjacency_matrix: &Matrix) -> Matrix {
+        let n = adjacency_matrix.size();
+        let mut pheromones = Matrix::new(n);
+        for i in 0..n {
+            for j in 0..n {
+                pheromones[i][j] = 1.0;
+            }
+        }
+        Ant::attractiveness(
+            adjacency_matrix,
+            &pheromones,
+            Exponents::default(),
+            &SavingsHeuristic,
+        )
+    }
+
+    #[test]
+    fn test_edge_weights_finite_for_large_coordinates() {
+        // Savings on the order of 10^40 overflow savings.powi(9) when exponentiated directly.
+        // Projected map coordinates reach 10^6, here with two customers almost on top of each
+        // other
+        let instances = [
+            vec![
+                (0.0, 0.0),
+                (1e40, 1e40),
+                (1e40, 2e40),
+                (2e40, 1e40),
+                (1e6, 1e6),
+            ],
+            vec![
+                (0.0, 0.0),
+                (1e6, 1e6),
+                (1e6, 1e6 + 1e-3),
+                (-1e6, 1e6),
+                (1e6, -1e6),
+            ],
+        ];
+        for coordinates in instances {
+            let adjacency_matrix = Matrix::adjacency(coordinates);
+            let attractiveness = uniform_attractiveness(&adjacency_matrix);
+
+            let mut ant = Ant::new(5, 100.0);
+            ant.visit(1);
+
+            let mut weights = Vec::new();
+            ant.edge_weights(&attractiveness, &[0.0; 5], &mut weights);
+            assert!(weights.iter().flatten().all(|w| w.is_finite()));
+            assert_eq!(weights.iter().flatten().cloned().fold(0.0, f64::max), 1.0);
+        }
+    }
+
+    #[test]
+    fn test_edge_weights_non_negative_for_negative_savings() {
+        // 1 -> 3 costs more than going through the depot, so its savings are negative
+        let mut adjacency_matrix = Matrix::new(4);
+        let distances = [
+            (0, 1, 10.0),
+            (0, 2, 10.0),
+            (0, 3, 10.0),
+            (1, 2, 5.0),
+            (1, 3, 50.0),
+            (2, 3, 5.0),
+        ];
+        for (i, j, distance) in distances {
+            adjacency_matrix[i][j] = distance;
+            adjacency_matrix[j][i] = distance;
+        }
+        let attractiveness = uniform_attractiveness(&adjacency_matrix);
+
+        let mut ant = Ant::new(4, 100.0);
+        ant.visit(1);
+
+        let mut weights = Vec::new();
+        ant.edge_weights(&attractiveness, &[0.0; 4], &mut weights);
+        assert!(weights.iter().flatten().all(|&w| w >= 0.0));
+    }
+
+    #[test]
+    fn test_edge_weights_skip_customers_over_capacity() {
+        let adjacency_matrix = Matrix::adjacency(vec![
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 10.0),
+            (0.0, 10.0),
+            (5.0, 5.0),
+        ]);
+        let attractiveness = uniform_attractiveness(&adjacency_matrix);
+        let demands = [0.0, 50.0, 60.0, 10.0, 40.0];
+
+        let mut ant = Ant::new(5, 100.0);
+        ant.visit(1);
+        ant.cur_capacity = 50.0;
+
+        let mut weights = Vec::new();
+        ant.edge_weights(&attractiveness, &demands, &mut weights);
+        assert_eq!(weights[2], None);
+        assert!(weights[3].is_some());
+        assert!(weights[4].is_some());
+    }
+
+    #[test]
+    fn test_zero_demand_customers_visited_once_without_depot_returns() {
+        let adjacency_matrix = Matrix::adjacency(vec![
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 10.0),
+            (0.0, 10.0),
+            (5.0, 5.0),
+        ]);
+        let attractiveness = uniform_attractiveness(&adjacency_matrix);
+        // The first customer fills the vehicle, the rest need no capacity
+        let demands = [0.0, 10.0, 0.0, 0.0, 0.0];
+
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut ant = Ant::new(5, 10.0);
+        while !ant.done() {
+            ant.move_to_next(&adjacency_matrix, &attractiveness, &demands, &mut rng)
+                .unwrap();
+        }
+        ant.complete(&adjacency_matrix);
+
+        let path = ant.path_taken();
+        for node in 1..5 {
+            assert_eq!(path.iter().filter(|&&n| n == node).count(), 1);
+        }
+        assert_eq!(path.iter().filter(|&&n| n == 0).count(), 2);
+    }
+
+    #[test]
+    fn test_greedy_ant_takes_heaviest_edges() {
+        let adjacency_matrix = Matrix::adjacency(vec![
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 10.0),
+            (0.0, 10.0),
+            (5.0, 5.0),
+        ]);
+        let attractiveness = uniform_attractiveness(&adjacency_matrix);
+        let demands = [0.0, 3.0, 3.0, 3.0, 3.0];
+
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut ant = Ant::new_greedy(5, 10.0);
+        while !ant.done() {
+            let mut weights = Vec::new();
+            ant.edge_weights(&attractiveness, &demands, &mut weights);
+            let heaviest = weights.iter().flatten().cloned().fold(0.0, f64::max);
+
+            ant.move_to_next(&adjacency_matrix, &attractiveness, &demands, &mut rng)
+                .unwrap();
+
+            if heaviest > 0.0 {
+                assert_eq!(weights[ant.cur_node()], Some(heaviest));
+            }
+        }
+    }
+
+    #[test]
+    fn test_greedy_tour_unchanged_by_precomputed_attractiveness() {
+        let adjacency_matrix = Matrix::adjacency(vec![
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 10.0),
+            (0.0, 10.0),
+            (5.0, 5.0),
+            (-4.0, 7.0),
+            (8.0, -6.0),
+            (-9.0, -3.0),
+        ]);
+        let n = adjacency_matrix.size();
+        // Uneven, so the tour follows the pheromones as well as the distances
+        let mut pheromones = Matrix::new(n);
+        for i in 0..n {
+            for j in 0..n {
+                pheromones[i][j] = 10f64.powi(((i * 7 + j * 3) % 5) as i32);
+            }
+        }
+        let demands = [0.0, 3.0, 4.0, 3.0, 4.0, 2.0, 5.0, 3.0];
+        let capacity = 10.0;
+
+        // As ants chose before the weights were computed once per cycle: every step weighs the
+        // edges out of the current node afresh, then takes the heaviest, ties going to the lowest
+        let mut expected = vec![0];
+        let mut visited = vec![false; n];
+        visited[0] = true;
+        let mut load = capacity;
+        while visited.iter().any(|&v| !v) {
+            let from = *expected.last().unwrap();
+            if from == 0 {
+                load = capacity;
+            }
+            let log_weights: Vec<(usize, f64)> = (0..n)
+                .filter(|&to| !visited[to] && demands[to] <= load)
+                .map(|to| {
+                    let weight = SavingsHeuristic.edge_weight(&EdgeContext {
+                        from,
+                        to,
+                        distance: adjacency_matrix[from][to],
+                        distance_to_depot: adjacency_matrix[from][0],
+                        distance_from_depot: adjacency_matrix[0][to],
+                        pheromone: pheromones[from][to],
+                        exponents: Exponents::default(),
+                    });
+                    (to, weight)
+                })
+                .collect();
+            let max = log_weights
+                .iter()
+                .map(|&(_, w)| w)
+                .fold(f64::NEG_INFINITY, f64::max);
+            let next = log_weights
+                .iter()
+                .map(|&(to, w)| (to, (w - max).exp()))
+                .max_by(|(i, a), (j, b)| a.total_cmp(b).then(j.cmp(i)))
+                .map_or(0, |(to, _)| to);
+
+            expected.push(next);
+            visited[next] = true;
+            load -= demands[next];
+        }
+
+        let attractiveness = Ant::attractiveness(
+            &adjacency_matrix,
+            &pheromones,
+            Exponents::default(),
+            &SavingsHeuristic,
+        );
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut ant = Ant::new_greedy(n, capacity);
+        while !ant.done() {
+            ant.move_to_next(&adjacency_matrix, &attractiveness, &demands, &mut rng)
+                .unwrap();
+        }
+
+        // More than one route, so the returns to the depot are compared too
+        assert!(expected[1..].contains(&0));
+        assert_eq!(ant.path_taken(), &expected);
+    }
+
+    #[test]
+    fn test_reset_ant_builds_the_same_tour() {
+        let adjacency_matrix = Matrix::adjacency(vec![
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 10.0),
+            (0.0, 10.0),
+            (5.0, 5.0),
+        ]);
+        let attractiveness = uniform_attractiveness(&adjacency_matrix);
+        let demands = [0.0, 3.0, 4.0, 3.0, 4.0];
+        let build = |ant: &mut Ant| {
+            let mut rng = ChaCha8Rng::seed_from_u64(0);
+            while !ant.done() {
+                ant.move_to_next(&adjacency_matrix, &attractiveness, &demands, &mut rng)
+                    .unwrap();
+            }
+            ant.complete(&adjacency_matrix);
+        };
+
+        let mut fresh = Ant::new_greedy(5, 10.0);
+        build(&mut fresh);
+        let mut reused = Ant::new_greedy(5, 10.0);
+        build(&mut reused);
+        reused.reset();
+        assert_eq!(reused.path_taken(), &[0]);
+        build(&mut reused);
+
+        assert_eq!(reused.path_taken(), fresh.path_taken());
+        assert_eq!(reused.path_cost(), fresh.path_cost());
+    }
+
+    #[test]
+    fn test_depot_return() {
+        let adjacency_matrix = Matrix::adjacency(vec![
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 10.0),
+            (0.0, 10.0),
+            (5.0, 5.0),
+        ]);
+        let attractiveness = uniform_attractiveness(&adjacency_matrix);
+        let demands = [0.0, 2.0, 2.0, 2.0, 2.0];
+        let always = DepotReturn {
+            fill: 0.5,
+            probability: 1.0,
+        };
+
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut ant = Ant::new(5, 8.0).with_depot_return(Some(always));
+        while !ant.done() {
+            ant.move_to_next(&adjacency_matrix, &attractiveness, &demands, &mut rng)
+                .unwrap();
+        }
+
+        // Everything fits in one vehicle, but it goes back after every second customer
+        assert_eq!(
+            ant.path_taken().iter().filter(|&&node| node == 0).count(),
+            2
+        );
+    }
+
+    fn pick(distribution: &[Option<f64>], rand: f64) -> usize {
+        Roulette::default().pick(distribution, rand)
+    }
+
+    #[test]
+    fn test_roulette_selection() {
+        let distribution = [None, Some(1.0), None, Some(0.0), Some(3.0)];
+
+        assert_eq!(pick(&distribution, 0.0), 1);
+        assert_eq!(pick(&distribution, 0.25), 1);
+        assert_eq!(pick(&distribution, 0.26), 4);
+        // Past the total, as rounding can cause
+        assert_eq!(pick(&distribution, 1.0 + 1e-12), 4);
+    }
+
+    #[test]
+    fn test_equal_weights_break_ties_by_index() {
+        let distribution = [None, Some(2.0), None, Some(2.0), Some(2.0)];
+
+        // Each node gets a third of the range, lowest index first
+        assert_eq!(pick(&distribution, 0.0), 1);
+        assert_eq!(pick(&distribution, 0.33), 1);
+        assert_eq!(pick(&distribution, 0.34), 3);
+        assert_eq!(pick(&distribution, 0.66), 3);
+        assert_eq!(pick(&distribution, 0.67), 4);
+
+        // On a boundary the lower node wins
+        let halves = [Some(1.0), Some(1.0)];
+        assert_eq!(pick(&halves, 0.5), 0);
+    }
+
+    #[test]
+    fn test_roulette_reused_between_picks() {
+        let mut roulette = Roulette::default();
+        assert_eq!(roulette.pick(&[Some(1.0), Some(1.0), Some(1.0)], 1.0), 2);
+
+        // Nothing is left over from the longer distribution before
+        assert_eq!(roulette.pick(&[None, Some(1.0)], 1.0), 1);
+        assert_eq!(roulette.candidates, [1]);
+        assert_eq!(roulette.cumulative, [1.0]);
+    }
+
+    mod selection {
+        use proptest::{collection::vec, option, prelude::*};
+
+        use super::*;
+
+        /// The first node whose running total reaches `rand * total`, found by summing the
+        /// weights one at a time, or the last node if rounding leaves the threshold past them all.
+        fn scanned_selection(distribution: &[Option<f64>], rand: f64) -> usize {
+            let total: f64 = distribution.iter().flatten().sum();
+            let threshold = rand * total;
+
+            let mut cumulative = 0.0;
+            let mut last = 0;
+            for (i, weight) in distribution.iter().enumerate() {
+                if let Some(weight) = weight {
+                    cumulative += weight;
+                    if cumulative >= threshold {
+                        return i;
+                    }
+                    last = i;
+                }
+            }
+            last
+        }
+
+        /// Weights as edge_weights leaves them, at most 1 and some exactly 0, with at least one
+        /// node to choose.
+        fn distributions() -> impl Strategy<Value = Vec<Option<f64>>> {
+            vec(
+                option::of(prop_oneof![Just(0.0), Just(1.0), 0.0..=1.0]),
+                1..20,
+            )
+            .prop_filter("a node to choose", |weights| {
+                weights.iter().any(Option::is_some)
+            })
+        }
+
+        proptest! {
+            #[test]
+            fn test_matches_scanned_selection(
+                distribution in distributions(),
+                rand in prop_oneof![Just(0.0), Just(1.0), 0.0..1.0],
+            ) {
+                prop_assert_eq!(
+                    pick(&distribution, rand),
+                    scanned_selection(&distribution, rand)
+                );
+            }
+        }
+    }
+}
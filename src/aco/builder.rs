@@ -0,0 +1,338 @@
+use anyhow::{anyhow, bail, Result};
+use yoos::collections::Matrix;
+
+use super::{
+    matrix::MatrixExt,
+    problem::{EdgeWeightType, Problem, ProblemType},
+};
+
+/// Distances differing by less than this in each direction still count as symmetric.
+const SYMMETRY_EPSILON: f64 = 1e-9;
+
+/// Assembles a [`Problem`] in memory, checking that the pieces fit together when built.
+///
+/// Distances come from either [`ProblemBuilder::coordinates`], measured with the edge weight
+/// type's metric, or [`ProblemBuilder::matrix`], which makes the instance `EXPLICIT`.
+#[derive(Default)]
+pub struct ProblemBuilder {
+    name: String,
+    comment: String,
+    problem_type: Option<ProblemType>,
+    dimension: Option<usize>,
+    edge_weight_type: Option<EdgeWeightType>,
+    capacity: Option<f64>,
+    coordinates: Option<Vec<(f64, f64)>>,
+    elevations: Vec<f64>,
+    matrix: Option<Matrix>,
+    demands: Vec<f64>,
+    service_times: Vec<f64>,
+    depot: usize,
+}
+
+impl ProblemBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = comment.into();
+        self
+    }
+
+    /// The kind of problem, CVRP if not given.
+    pub fn problem_type(mut self, problem_type: ProblemType) -> Self {
+        self.problem_type = Some(problem_type);
+        self
+    }
+
+    /// The number of nodes, including the depot. Taken from the demands if not given.
+    pub fn dimension(mut self, dimension: usize) -> Self {
+        self.dimension = Some(dimension);
+        self
+    }
+
+    /// How to measure distances between coordinates, EUC_2D if not given.
+    pub fn edge_weight_type(mut self, edge_weight_type: EdgeWeightType) -> Self {
+        self.edge_weight_type = Some(edge_weight_type);
+        self
+    }
+
+    /// How much each vehicle carries. Required for CVRP, while TSP defaults to unlimited.
+    pub fn capacity(mut self, capacity: f64) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    pub fn coordinates(mut self, coordinates: Vec<(f64, f64)>) -> Self {
+        self.coordinates = Some(coordinates);
+        self
+    }
+
+    /// The z coordinate of each node, required with EUC_3D and ignored otherwise.
+    pub fn elevations(mut self, elevations: Vec<f64>) -> Self {
+        self.elevations = elevations;
+        self
+    }
+
+    pub fn matrix(mut self, matrix: Matrix) -> Self {
+        self.matrix = Some(matrix);
+        self
+    }
+
+    /// The demand of each node. Required for CVRP, while TSP defaults to all zeros.
+    pub fn demands(mut self, demands: Vec<f64>) -> Self {
+        self.demands = demands;
+        self
+    }
+
+    /// How long a vehicle spends at each node, if known.
+    pub fn service_times(mut self, service_times: Vec<f64>) -> Self {
+        self.service_times = service_times;
+        self
+    }
+
+    /// Which node is the depot, 0 if not given. The solver always starts from node 0, so a
+    /// different depot trades places with node 0 when built.
+    pub fn depot(mut self, depot: usize) -> Self {
+        self.depot = depot;
+        self
+    }
+
+    pub fn build(self) -> Result<Problem> {
+        let problem_type = self.problem_type.unwrap_or(ProblemType::Cvrp);
+        let tsp = matches!(problem_type, ProblemType::Tsp);
+
+        let mut demands = self.demands;
+        if tsp && demands.is_empty() {
+            let nodes = self
+                .dimension
+                .or_else(|| self.coordinates.as_ref().map(Vec::len))
+                .or_else(|| self.matrix.as_ref().map(Matrix::size))
+                .unwrap_or(0);
+            demands = vec![0.0; nodes];
+        }
+
+        let dimension = self.dimension.unwrap_or(demands.len());
+        let capacity = match self.capacity {
+            Some(capacity) => capacity,
+            // With nothing to carry, ants only return to the depot once every node is visited
+            None if tsp => f64::INFINITY,
+            None => bail!("A capacity is required"),
+        };
+
+        if dimension == 0 {
+            bail!("An instance needs at least one node, the depot");
+        }
+        if demands.len() != dimension {
+            bail!("Expected {} demands, got {}", dimension, demands.len());
+        }
+        if capacity.is_nan() || capacity <= 0.0 {
+            bail!("Capacity must be positive, got {}", capacity);
+        }
+        // The instance format gives the comment a single line
+        if self.comment.contains(['\n', '\r']) {
+            bail!("The comment can't span several lines");
+        }
+        if let Some(node) = demands.iter().position(|&demand| demand < 0.0) {
+            bail!("Node {} has a negative demand", node + 1);
+        }
+        if self.depot >= dimension {
+            bail!("Depot {} is not one of the {} nodes", self.depot, dimension);
+        }
+        demands.swap(0, self.depot);
+
+        let mut service_times = self.service_times;
+        if !service_times.is_empty() && service_times.len() != dimension {
+            bail!(
+                "Expected {} service times, got {}",
+                dimension,
+                service_times.len()
+            );
+        }
+        if let Some(node) = service_times.iter().position(|&time| time < 0.0) {
+            bail!("Node {} has a negative service time", node + 1);
+        }
+        if !service_times.is_empty() {
+            service_times.swap(0, self.depot);
+        }
+
+        let mut elevations = self.elevations;
+        let (edge_weight_type, coordinates, adjacency_matrix) =
+            match (self.coordinates, self.matrix) {
+                (Some(_), Some(_)) => bail!("Give either coordinates or a matrix, not both"),
+                (None, None) => bail!("Either coordinates or a matrix is required"),
+                (Some(mut coordinates), None) => {
+                    if coordinates.len() != dimension {
+                        bail!(
+                            "Expected {} coordinates, got {}",
+                            dimension,
+                            coordinates.len()
+                        );
+                    }
+                    coordinates.swap(0, self.depot);
+
+                    let edge_weight_type = self.edge_weight_type.unwrap_or(EdgeWeightType::Euc2d);
+                    let matrix = if let EdgeWeightType::Euc3d = edge_weight_type {
+                        if elevations.len() != dimension {
+                            bail!(
+                                "Expected {} elevations, got {}",
+                                dimension,
+                                elevations.len()
+                            );
+                        }
+                        elevations.swap(0, self.depot);
+
+                        let points: Vec<_> = coordinates
+                            .iter()
+                            .zip(&elevations)
+                            .map(|(&(x, y), &z)| (x, y, z))
+                            .collect();
+                        Matrix::from_coordinates_3d(&points)
+                    } else {
+                        elevations.clear();
+                        let metric = edge_weight_type.metric().ok_or_else(|| {
+                            anyhow!("EXPLICIT instances need a matrix, not coordinates")
+                        })?;
+                        Matrix::from_coordinates(&coordinates, metric)
+                    };
+                    (edge_weight_type, coordinates, matrix)
+                }
+                (None, Some(mut matrix)) => {
+                    if matrix.size() != dimension {
+                        bail!(
+                            "Expected a {0}x{0} matrix, got {1}x{1}",
+                            dimension,
+                            matrix.size()
+                        );
+                    }
+                    swap_nodes(&mut matrix, 0, self.depot);
+                    elevations.clear();
+                    (EdgeWeightType::Explicit, Vec::new(), matrix)
+                }
+            };
+
+        let symmetric = adjacency_matrix.is_symmetric(SYMMETRY_EPSILON);
+
+        Ok(Problem {
+            name: self.name,
+            comment: self.comment,
+            problem_type,
+            dimension,
+            edge_weight_type,
+            capacity,
+            coordinates,
+            elevations,
+            adjacency_matrix,
+            symmetric,
+            demands,
+            service_times,
+        })
+    }
+}
+
+/// Swaps the rows and columns of nodes `a` and `b`, as though they had been given in each
+/// other's place.
+fn swap_nodes(matrix: &mut Matrix, a: usize, b: usize) {
+    let row_a = matrix[a].to_vec();
+    let row_b = matrix[b].to_vec();
+    matrix[a].copy_from_slice(&row_b);
+    matrix[b].copy_from_slice(&row_a);
+
+    for k in 0..matrix.size() {
+        matrix[k].swap(a, b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_moves_depot_first() -> Result<()> {
+        let problem = ProblemBuilder::new()
+            .capacity(10.0)
+            .coordinates(vec![(3.0, 4.0), (0.0, 0.0), (6.0, 8.0)])
+            .demands(vec![5.0, 0.0, 5.0])
+            .depot(1)
+            .build()?;
+
+        assert_eq!(problem.dimension, 3);
+        assert_eq!(problem.coordinates[0], (0.0, 0.0));
+        assert_eq!(problem.demands, vec![0.0, 5.0, 5.0]);
+        assert_eq!(problem.adjacency_matrix[0][2], 10.0);
+        assert!(problem.symmetric);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_rejects_mismatched_sizes() {
+        let problem = ProblemBuilder::new()
+            .dimension(3)
+            .capacity(10.0)
+            .coordinates(vec![(0.0, 0.0), (3.0, 4.0)])
+            .demands(vec![0.0, 5.0, 5.0])
+            .build();
+
+        assert!(problem.is_err());
+    }
+
+    #[test]
+    fn test_build_rejects_nan_capacity() {
+        let problem = ProblemBuilder::new()
+            .capacity(f64::NAN)
+            .coordinates(vec![(0.0, 0.0), (3.0, 4.0)])
+            .demands(vec![0.0, 5.0])
+            .build();
+
+        assert!(problem.is_err());
+    }
+
+    #[test]
+    fn test_build_rejects_multiline_comment() {
+        let problem = ProblemBuilder::new()
+            .comment("First line\nSecond line")
+            .capacity(10.0)
+            .coordinates(vec![(0.0, 0.0), (3.0, 4.0)])
+            .demands(vec![0.0, 5.0])
+            .build();
+
+        assert!(problem.is_err());
+    }
+
+    #[test]
+    fn test_build_detects_asymmetric_matrix() -> Result<()> {
+        let mut matrix = Matrix::new(2);
+        matrix[0][1] = 1.0;
+        matrix[1][0] = 2.0;
+
+        let problem = ProblemBuilder::new()
+            .capacity(10.0)
+            .matrix(matrix)
+            .demands(vec![0.0, 5.0])
+            .build()?;
+
+        assert!(!problem.symmetric);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_tsp_without_capacity_or_demands() -> Result<()> {
+        let problem = ProblemBuilder::new()
+            .problem_type(ProblemType::Tsp)
+            .coordinates(vec![(0.0, 0.0), (3.0, 4.0), (6.0, 8.0)])
+            .build()?;
+
+        assert_eq!(problem.dimension, 3);
+        assert_eq!(problem.capacity, f64::INFINITY);
+        assert_eq!(problem.demands, vec![0.0; 3]);
+
+        Ok(())
+    }
+}
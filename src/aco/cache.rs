@@ -0,0 +1,231 @@
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io::{BufReader, BufWriter, Read};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use fnv::FnvHasher;
+use serde::{Deserialize, Serialize};
+use yoos::collections::Matrix;
+
+use super::{matrix::MatrixExt, problem::Problem};
+
+/// Bumped whenever [`CachedProblem`] changes, so that entries written in an older format are
+/// parsed afresh rather than misread.
+const CACHE_FORMAT_VERSION: u32 = 1;
+
+/// How many entries a cache directory keeps before the oldest are removed.
+const MAX_ENTRIES: usize = 64;
+
+/// Everything in a [`Problem`], in a form bincode can write. The enums are kept as the strings
+/// TSPLIB names them with.
+#[derive(Serialize, Deserialize)]
+struct CachedProblem {
+    name: String,
+    comment: String,
+    problem_type: String,
+    dimension: usize,
+    edge_weight_type: String,
+    capacity: f64,
+    coordinates: Vec<(f64, f64)>,
+    elevations: Vec<f64>,
+    matrix: Vec<Vec<f64>>,
+    symmetric: bool,
+    demands: Vec<f64>,
+    service_times: Vec<f64>,
+}
+
+impl CachedProblem {
+    fn new(problem: &Problem) -> Self {
+        let matrix = &problem.adjacency_matrix;
+        Self {
+            name: problem.name.clone(),
+            comment: problem.comment.clone(),
+            problem_type: problem.problem_type.to_string(),
+            dimension: problem.dimension,
+            edge_weight_type: problem.edge_weight_type.to_string(),
+            capacity: problem.capacity,
+            coordinates: problem.coordinates.clone(),
+            elevations: problem.elevations.clone(),
+            matrix: (0..matrix.size()).map(|i| matrix[i].to_vec()).collect(),
+            symmetric: problem.symmetric,
+            demands: problem.demands.clone(),
+            service_times: problem.service_times.clone(),
+        }
+    }
+
+    fn into_problem(self) -> Result<Problem> {
+        // A corrupt entry fails here rather than panicking
+        let adjacency_matrix = Matrix::try_from_rows(self.matrix)?;
+
+        Ok(Problem {
+            name: self.name,
+            comment: self.comment,
+            problem_type: self.problem_type.parse()?,
+            dimension: self.dimension,
+            edge_weight_type: self.edge_weight_type.parse()?,
+            capacity: self.capacity,
+            coordinates: self.coordinates,
+            elevations: self.elevations,
+            adjacency_matrix,
+            symmetric: self.symmetric,
+            demands: self.demands,
+            service_times: self.service_times,
+        })
+    }
+}
+
+/// Reads an instance like [`Problem::from_reader`], but keeps the parsed instance, distance
+/// matrix included, in `dir` so that reading the same instance again skips parsing and building
+/// the matrix. Entries are keyed by a hash of the instance's text, so an edited instance is
+/// parsed afresh rather than read stale from the cache. Writing an entry removes any in an older
+/// format, and the oldest once there are more than 64.
+///
+/// The cache is only an optimization: a missing or unreadable entry means parsing as normal, and
+/// failing to write one is only a warning.
+pub fn read_cached<R: Read>(mut reader: R, dir: &Path) -> Result<Problem> {
+    let mut contents = String::new();
+    reader.read_to_string(&mut contents)?;
+
+    let path = entry_path(&contents, dir);
+    if let Some(problem) = load(&path) {
+        return Ok(problem);
+    }
+
+    let problem = Problem::from_contents(&contents)?;
+    if let Err(err) = store(&problem, &path).and_then(|_| evict(dir, MAX_ENTRIES)) {
+        eprintln!("Warning: couldn't cache {}: {}", problem.name, err);
+    }
+    Ok(problem)
+}
+
+/// A hash of an instance's text that stays the same between builds, FNV rather than the
+/// standard library's hasher, whose output may change between Rust releases.
+pub(crate) fn content_hash(contents: &str) -> u64 {
+    let mut hasher = FnvHasher::default();
+    hasher.write(contents.as_bytes());
+    hasher.finish()
+}
+
+fn entry_path(contents: &str, dir: &Path) -> PathBuf {
+    dir.join(format!(
+        "{:016x}.v{}.bin",
+        content_hash(contents),
+        CACHE_FORMAT_VERSION
+    ))
+}
+
+/// Removes the entries in `dir` written in an older format, then the least recently written
+/// ones until at most `max_entries` are left.
+fn evict(dir: &Path, max_entries: usize) -> Result<()> {
+    let current = format!(".v{}.bin", CACHE_FORMAT_VERSION);
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        if name.ends_with(&current) {
+            entries.push((fs::metadata(&path)?.modified()?, path));
+        } else if name.ends_with(".bin") {
+            fs::remove_file(&path)?;
+        }
+    }
+
+    entries.sort();
+    let excess = entries.len().saturating_sub(max_entries);
+    for (_, path) in &entries[..excess] {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+fn load(path: &Path) -> Option<Problem> {
+    let file = File::open(path).ok()?;
+    let cached: CachedProblem = bincode::deserialize_from(BufReader::new(file)).ok()?;
+    cached.into_problem().ok()
+}
+
+fn store(problem: &Problem, path: &Path) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let file = File::create(path)?;
+    bincode::serialize_into(BufWriter::new(file), &CachedProblem::new(problem))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aco::builder::ProblemBuilder;
+
+    #[test]
+    fn test_read_cached() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("cvrp-aco-rs-test-{}", std::process::id()));
+        let mut vrp = Vec::new();
+        ProblemBuilder::new()
+            .name("cached")
+            .comment("Hand written")
+            .capacity(10.0)
+            .coordinates(vec![(0.0, 0.0), (3.0, 4.0), (6.0, 8.0)])
+            .demands(vec![0.0, 5.0, 5.0])
+            .build()?
+            .write_vrp(&mut vrp)?;
+
+        let parsed = read_cached(&vrp[..], &dir)?;
+        let path = entry_path(std::str::from_utf8(&vrp)?, &dir);
+        assert!(path.exists());
+
+        let cached = read_cached(&vrp[..], &dir)?;
+        assert_eq!(cached.name, parsed.name);
+        assert_eq!(cached.demands, parsed.demands);
+        assert!(cached
+            .adjacency_matrix
+            .entries()
+            .eq(parsed.adjacency_matrix.entries()));
+
+        // A changed instance gets an entry of its own
+        let edited = String::from_utf8(vrp)?.replace("cached", "edited");
+        assert_eq!(read_cached(edited.as_bytes(), &dir)?.name, "edited");
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_entry_path_is_stable() {
+        // Pinned so that a change of hash, which orphans every entry, is a deliberate one
+        assert_eq!(
+            entry_path("NAME : cached", Path::new("cache")),
+            Path::new("cache/6957e44654d47eb2.v1.bin")
+        );
+    }
+
+    #[test]
+    fn test_evict() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("cvrp-aco-rs-evict-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        for name in ["a.v1.bin", "b.v1.bin", "c.v1.bin", "d.v0.bin", "notes.txt"] {
+            File::create(dir.join(name))?;
+        }
+
+        evict(&dir, 2)?;
+        let mut left: Vec<_> = fs::read_dir(&dir)?
+            .map(|entry| Ok(entry?.file_name().into_string().unwrap()))
+            .collect::<Result<_>>()?;
+        left.sort();
+
+        // Which of a, b and c goes depends on the timestamps, which may well be equal
+        assert_eq!(left.len(), 3);
+        assert_eq!(
+            left.iter().filter(|name| name.ends_with(".v1.bin")).count(),
+            2
+        );
+        assert!(left.contains(&String::from("notes.txt")));
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+use yoos::collections::Matrix;
+
+#[cfg(feature = "kdtree")]
+use super::matrix::DistanceMetric;
+use super::{matrix::MatrixExt, problem::Problem};
+
+/// Each node's `k` nearest other nodes, closest first, or all of them if there are fewer than
+/// `k`.
+///
+/// With the `kdtree` feature, instances measured with a Euclidean or Manhattan metric find their
+/// neighbours with a k-d tree over the coordinates, which is much cheaper than sorting every row
+/// of the matrix on large instances. Everything else falls back to [`candidate_lists_from_matrix`].
+pub fn build_candidate_lists(problem: &Problem, k: usize) -> Vec<Vec<usize>> {
+    #[cfg(feature = "kdtree")]
+    {
+        let metric = problem.edge_weight_type.metric();
+        if let Some(metric @ (DistanceMetric::Euclidean | DistanceMetric::Manhattan)) = metric {
+            let matrix = &problem.adjacency_matrix;
+            return candidate_lists_from_coordinates(&problem.coordinates, matrix, metric, k);
+        }
+    }
+
+    candidate_lists_from_matrix(&problem.adjacency_matrix, k)
+}
+
+/// Candidate lists found by sorting each row of `matrix`, ties going to the lower node.
+pub fn candidate_lists_from_matrix(matrix: &Matrix, k: usize) -> Vec<Vec<usize>> {
+    (0..matrix.size()).map(|i| matrix.k_nearest(i, k)).collect()
+}
+
+/// Candidate lists found with a k-d tree, which decides between nodes at the same distance
+/// however its buckets happen to fall. So every node as close as the furthest it returns is
+/// gathered, then ranked by `matrix` and node as [`candidate_lists_from_matrix`] ranks them.
+#[cfg(feature = "kdtree")]
+fn candidate_lists_from_coordinates(
+    coordinates: &[(f64, f64)],
+    matrix: &Matrix,
+    metric: DistanceMetric,
+    k: usize,
+) -> Vec<Vec<usize>> {
+    use kdtree::{distance::squared_euclidean, KdTree};
+
+    let mut tree = KdTree::with_capacity(2, coordinates.len());
+    for (node, &(x, y)) in coordinates.iter().enumerate() {
+        // Only fails on non-finite coordinates, which make every distance meaningless anyway
+        tree.add([x, y], node).unwrap();
+    }
+
+    let manhattan = |a: &[f64], b: &[f64]| (a[0] - b[0]).abs() + (a[1] - b[1]).abs();
+
+    coordinates
+        .iter()
+        .enumerate()
+        .map(|(i, &(x, y))| {
+            let distance: &dyn Fn(&[f64], &[f64]) -> f64 = match metric {
+                DistanceMetric::Manhattan => &manhattan,
+                _ => &squared_euclidean,
+            };
+            // One extra, since the node finds itself
+            let nearest = tree.nearest(&[x, y], k + 1, &distance).unwrap();
+            let furthest = nearest.last().map_or(0.0, |&(distance, _)| distance);
+            // A little further, in case the tree's distances round differently to the matrix's
+            let tied = tree
+                .within(&[x, y], furthest * (1.0 + 1e-9), &distance)
+                .unwrap();
+
+            let mut neighbours: Vec<usize> = tied
+                .into_iter()
+                .map(|(_, &node)| node)
+                .filter(|&node| node != i)
+                .collect();
+            neighbours.sort_by(|&a, &b| matrix[i][a].total_cmp(&matrix[i][b]).then(a.cmp(&b)));
+            neighbours.truncate(k);
+            neighbours
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aco::builder::ProblemBuilder;
+
+    #[test]
+    fn test_candidate_lists_match_brute_force() -> anyhow::Result<()> {
+        let problem = ProblemBuilder::new()
+            .capacity(10.0)
+            .coordinates(vec![
+                (0.0, 0.0),
+                (1.0, 7.0),
+                (4.0, 2.0),
+                (9.0, 9.0),
+                (3.0, 11.0),
+                (8.0, 1.5),
+                (6.0, 6.0),
+            ])
+            .demands(vec![0.0; 7])
+            .build()?;
+
+        let candidates = build_candidate_lists(&problem, 3);
+
+        assert_eq!(
+            candidates,
+            candidate_lists_from_matrix(&problem.adjacency_matrix, 3)
+        );
+        assert_eq!(candidates[0], vec![2, 1, 5]);
+
+        Ok(())
+    }
+
+    mod ties {
+        use proptest::{collection::vec, prelude::*};
+
+        use super::*;
+        use crate::aco::problem::{EdgeWeightType, Problem};
+
+        /// Instances on a grid small enough that many nodes are the same distance apart, some
+        /// even on top of each other.
+        fn problems() -> impl Strategy<Value = Problem> {
+            (any::<bool>(), vec((0..4u32, 0..4u32), 2..30)).prop_map(|(manhattan, coordinates)| {
+                let n = coordinates.len();
+                ProblemBuilder::new()
+                    .edge_weight_type(if manhattan {
+                        EdgeWeightType::Man2d
+                    } else {
+                        EdgeWeightType::Euc2d
+                    })
+                    .capacity(10.0)
+                    .coordinates(
+                        coordinates
+                            .into_iter()
+                            .map(|(x, y)| (x as f64, y as f64))
+                            .collect(),
+                    )
+                    .demands(vec![0.0; n])
+                    .build()
+                    .unwrap()
+            })
+        }
+
+        proptest! {
+            #[test]
+            fn test_ties_break_the_same_way(problem in problems(), k in 1..12usize) {
+                prop_assert_eq!(
+                    build_candidate_lists(&problem, k),
+                    candidate_lists_from_matrix(&problem.adjacency_matrix, k)
+                );
+            }
+        }
+    }
+}
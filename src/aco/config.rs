@@ -0,0 +1,265 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    ant::Exponents,
+    heuristic::{ConstructionHeuristic, SavingsHeuristic},
+    opt::LocalSearch,
+    sim::MAX_CYCLES,
+    termination::{Any, MaxCycles, OptimumReached, Stagnation, TerminationCriterion},
+    utils::format_cost,
+};
+
+/// Tunable parameters of a run. The defaults reproduce the original, untuned behaviour.
+///
+/// Serializing skips the heuristic and termination criterion, which can be any implementation
+/// of their traits, so they read back as the defaults.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SimulatorConfig {
+    /// Added to a tour's cost for every vehicle it uses when comparing tours, trading some
+    /// distance for fewer routes. Zero compares on distance alone.
+    pub vehicle_penalty: f64,
+
+    /// Added to a tour's cost times the variance of its routes' loads when comparing tours,
+    /// favouring tours that share the demand evenly between vehicles. Zero ignores the loads.
+    pub load_variance_penalty: f64,
+
+    /// What to do once the best tour has gone too many cycles without improving.
+    pub on_stagnation: StagnationPolicy,
+
+    /// Print each route with the depot at both ends, as in `0 1 2 3 0`, rather than just the
+    /// customers it visits.
+    pub depot_bookends: bool,
+
+    /// Explore more the longer the best tour goes without improving, by weakening the pull of the
+    /// pheromones and evaporating them faster. Both return to normal once a better tour is found.
+    pub adaptive: bool,
+
+    /// Move the exponents ants weigh edges with from one set to another over the run, rather
+    /// than always using the defaults. Unlike `adaptive`, this follows a fixed schedule whatever
+    /// the search finds, though `adaptive` still weakens the pheromone's on top of it.
+    pub exponent_schedule: Option<ExponentSchedule>,
+
+    /// The pheromone the best ant of a cycle lays along its tour, divided by the tour's cost.
+    /// The next best ants lay less the lower they rank, see `ranked_ants`.
+    pub deposit_q: f64,
+
+    /// How many of the best ants of a cycle lay pheromone, including the best. The ant ranked
+    /// `r`, counting the best as 0, lays `(ranked_ants - r) / ranked_ants` as much as the best.
+    /// The best ant always lays, so 0 counts as 1.
+    pub ranked_ants: usize,
+
+    /// Let ants return to the depot early once their vehicle is nearly full, to avoid a long
+    /// detour for a last customer that happens to fit. Routes only end when nothing else fits if
+    /// `None`.
+    pub depot_return: Option<DepotReturn>,
+
+    /// The furthest any one vehicle may drive, counting the way back to the depot. Unlimited if
+    /// `None`.
+    pub max_route_distance: Option<f64>,
+
+    /// How ants weigh the edges they choose between.
+    #[serde(skip, default = "default_heuristic")]
+    pub heuristic: Arc<dyn ConstructionHeuristic>,
+
+    /// The local search each ant runs on its tour before the tours are compared.
+    pub local_search: LocalSearch,
+
+    /// Which of each cycle's ants run the local search, as ranked by their tours before it.
+    pub local_search_ants: LocalSearchAnts,
+
+    /// Only run the local search every this many cycles, starting with the first. Tours on the
+    /// cycles in between are compared as the ants built them.
+    pub local_search_every_n_cycles: usize,
+
+    /// Once the run ends, polish the best tour with 2-opt and Or-opt between routes, a more
+    /// thorough search than the ants can afford every cycle, since it only runs the once.
+    pub final_polish: bool,
+
+    /// Make one ant each cycle always take the most attractive edge, so that a reasonable tour
+    /// is found on the first cycle without relying on luck.
+    pub greedy_ant: bool,
+
+    /// When warm starting, lay pheromone along the initial tour as though the star ant had taken
+    /// it. Without, the initial tour is only the one to beat and the colony searches afresh.
+    pub warm_start_pheromone: bool,
+
+    /// Keep every tour found that ties with the best but drives different routes, as
+    /// alternatives to choose between, see [`Simulator::run_all`].
+    ///
+    /// [`Simulator::run_all`]: super::Simulator::run_all
+    pub collect_ties: bool,
+
+    /// Count how many ants drive along each edge every cycle, which shows how far the colony
+    /// agrees on a tour. The counts from the last cycle end up in the solution.
+    pub track_edge_usage: bool,
+
+    /// How costs are printed while running.
+    pub cost_format: CostFormat,
+
+    /// Print nothing while running, leaving any reporting to the caller.
+    pub quiet: bool,
+
+    /// How much of the pheromone is kept each cycle.
+    pub evaporation: Evaporation,
+
+    /// When to end the run. By default after 149 cycles, more than 75 cycles without
+    /// improving, or on reaching the known optimum, whichever comes first.
+    #[serde(skip, default = "default_termination")]
+    pub termination: Arc<dyn TerminationCriterion>,
+
+    /// How much better a tour must be than the best so far to count as an improvement, so that
+    /// floating point noise doesn't keep resetting the stagnation count.
+    pub improvement_epsilon: f64,
+
+    /// Seeds the random choices the ants make, so that the same seed on the same instance and
+    /// config repeats a run exactly. Without one, each run picks its own.
+    pub seed: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum StagnationPolicy {
+    /// Keep going as normal, leaving it to the termination criterion to end the run, which the
+    /// default one does.
+    Stop,
+    /// Reset the pheromones to their initial values and keep searching, remembering only the
+    /// best tour found so far.
+    Restart,
+    /// Once the best tour has gone more than `after` cycles without improving, move every
+    /// pheromone `delta` of the way towards the strongest each cycle, as in MAX-MIN Ant System's
+    /// trail smoothing. This keeps the order the colony has learned between edges while letting
+    /// ants explore the weaker ones again.
+    Smooth { after: usize, delta: f64 },
+}
+
+/// Exponents moving in a straight line from `start` on the first cycle to `end` on cycle
+/// `cycles`, staying at `end` after that, see [`SimulatorConfig::exponent_schedule`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExponentSchedule {
+    pub start: Exponents,
+    pub end: Exponents,
+    pub cycles: usize,
+}
+
+impl ExponentSchedule {
+    /// The exponents for the cycle this many cycles into the run, the first being 0.
+    pub fn at(&self, cycle: usize) -> Exponents {
+        let t = if self.cycles <= 1 {
+            1.0
+        } else {
+            (cycle as f64 / (self.cycles - 1) as f64).min(1.0)
+        };
+        let lerp = |start: f64, end: f64| start + (end - start) * t;
+        Exponents {
+            savings: lerp(self.start.savings, self.end.savings),
+            pheromone: lerp(self.start.pheromone, self.end.pheromone),
+            distance: lerp(self.start.distance, self.end.distance),
+        }
+    }
+}
+
+/// Which ants run the local search, see [`SimulatorConfig::local_search_ants`]. Searching fewer
+/// of them is much cheaper on large instances, and usually finds most of the same improvement.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LocalSearchAnts {
+    All,
+    /// Only the ant with the best tour.
+    BestOnly,
+    /// Only the ants with the `k` best tours.
+    TopK(usize),
+}
+
+/// Lets ants close a route that is nearly full rather than only when nothing else fits, see
+/// [`SimulatorConfig::depot_return`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DepotReturn {
+    /// How full, as a fraction of the capacity, the vehicle must be before the ant considers
+    /// returning early.
+    pub fill: f64,
+    /// The chance of returning at each step once the vehicle is that full.
+    pub probability: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Evaporation {
+    /// Keep `0.5 + 80 / c` of it, where `c` is the average cost of the cycle's tours.
+    Cost,
+    /// Keep whatever share steers the pheromone's normalized entropy towards `target`, where 0
+    /// is all of it on one edge and 1 is spread evenly over every edge. Each cycle the share
+    /// kept moves by `gain` times how far the entropy is from the target, so a colony
+    /// converging too fast keeps more and one wandering aimlessly keeps less.
+    Entropy { target: f64, gain: f64 },
+}
+
+/// How many decimal places to print costs with, and what unit to label them with, e.g. `km`.
+/// Whole numbers are printed without any decimals.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CostFormat {
+    pub precision: usize,
+    pub unit: Option<String>,
+}
+
+impl Default for CostFormat {
+    fn default() -> Self {
+        Self {
+            precision: 2,
+            unit: None,
+        }
+    }
+}
+
+impl CostFormat {
+    pub fn format(&self, cost: f64) -> String {
+        let cost = format_cost(cost, self.precision);
+        match &self.unit {
+            Some(unit) => format!("{} {}", cost, unit),
+            None => cost,
+        }
+    }
+}
+
+fn default_heuristic() -> Arc<dyn ConstructionHeuristic> {
+    Arc::new(SavingsHeuristic)
+}
+
+fn default_termination() -> Arc<dyn TerminationCriterion> {
+    Arc::new(Any(vec![
+        // The original loop stopped before running its last cycle
+        Arc::new(MaxCycles(MAX_CYCLES - 1)),
+        Arc::new(Stagnation(MAX_CYCLES / 2)),
+        Arc::new(OptimumReached),
+    ]))
+}
+
+impl Default for SimulatorConfig {
+    fn default() -> Self {
+        Self {
+            vehicle_penalty: 0.0,
+            load_variance_penalty: 0.0,
+            on_stagnation: StagnationPolicy::Stop,
+            depot_bookends: false,
+            adaptive: false,
+            exponent_schedule: None,
+            deposit_q: 3.0,
+            ranked_ants: 3,
+            depot_return: None,
+            max_route_distance: None,
+            heuristic: default_heuristic(),
+            local_search: LocalSearch::TwoOpt,
+            local_search_ants: LocalSearchAnts::All,
+            local_search_every_n_cycles: 1,
+            final_polish: false,
+            greedy_ant: false,
+            warm_start_pheromone: true,
+            collect_ties: false,
+            track_edge_usage: false,
+            cost_format: CostFormat::default(),
+            quiet: false,
+            evaporation: Evaporation::Cost,
+            termination: default_termination(),
+            improvement_epsilon: 1e-9,
+            seed: None,
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::{bail, Result};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::{builder::ProblemBuilder, problem::Problem};
+
+/// How far nodes are placed from the origin along each axis.
+const BOX_SIZE: u32 = 100;
+
+/// A random instance with `n` customers and the depot placed uniformly at whole number
+/// coordinates in a 100 by 100 box, each customer demanding a whole number between 1 and a
+/// quarter of the capacity. The same seed always gives the same instance, on any platform, and
+/// the instance can be shared with [`Problem::write_vrp`]. Fails if the capacity is below 1, as
+/// no customer's demand would fit.
+pub fn generate_random(n: usize, capacity: f64, seed: u64) -> Result<Problem> {
+    if capacity.is_nan() || capacity < 1.0 {
+        bail!(
+            "Capacity must be at least 1 to fit any demand, got {}",
+            capacity
+        );
+    }
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let max_demand = ((capacity / 4.0) as u32).max(1);
+
+    let coordinates = (0..=n)
+        .map(|_| {
+            let x = rng.gen_range(0..=BOX_SIZE);
+            let y = rng.gen_range(0..=BOX_SIZE);
+            (x as f64, y as f64)
+        })
+        .collect();
+    let demands = std::iter::once(0.0)
+        .chain((0..n).map(|_| rng.gen_range(1..=max_demand) as f64))
+        .collect();
+
+    ProblemBuilder::new()
+        .name(format!("random-n{}-s{}", n + 1, seed))
+        .comment(format!("Generated with seed {}", seed))
+        .capacity(capacity)
+        .coordinates(coordinates)
+        .demands(demands)
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aco::matrix::MatrixExt;
+
+    #[test]
+    fn test_generate_random() -> Result<()> {
+        let problem = generate_random(20, 100.0, 7)?;
+
+        assert_eq!(problem.dimension, 21);
+        assert_eq!(problem.demands[0], 0.0);
+        assert!(problem.demands[1..]
+            .iter()
+            .all(|&d| (1.0..=25.0).contains(&d)));
+        assert_eq!(
+            problem.coordinates,
+            generate_random(20, 100.0, 7)?.coordinates
+        );
+        assert_ne!(
+            problem.coordinates,
+            generate_random(20, 100.0, 8)?.coordinates
+        );
+
+        // Small capacities still fit every demand, and smaller ones are refused
+        let tight = generate_random(5, 1.0, 7)?;
+        assert!(tight.demands[1..].iter().all(|&d| d == 1.0));
+        assert!(generate_random(5, 0.5, 7).is_err());
+        assert!(generate_random(5, f64::NAN, 7).is_err());
+
+        let mut vrp = Vec::new();
+        problem.write_vrp(&mut vrp)?;
+        let parsed = Problem::from_contents(std::str::from_utf8(&vrp)?)?;
+        assert_eq!(parsed.demands, problem.demands);
+        assert!(parsed
+            .adjacency_matrix
+            .entries()
+            .eq(problem.adjacency_matrix.entries()));
+
+        Ok(())
+    }
+}
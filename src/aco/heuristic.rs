@@ -0,0 +1,143 @@
+use std::fmt::Debug;
+
+use super::ant::Exponents;
+
+const MIN_SAVINGS: f64 = 1e-9;
+/// Nodes at the same coordinates are this far apart as far as weighing edges goes, so that the
+/// inverse distance between them is large rather than infinite.
+const MIN_DISTANCE: f64 = 1e-9;
+
+/// The log of the inverse distance, floored so that colocated nodes, e.g. a customer at the
+/// depot, don't give an infinite weight that would swamp every other edge.
+fn ln_inverse_distance(distance: f64) -> f64 {
+    -distance.max(MIN_DISTANCE).ln()
+}
+
+/// What is known about an edge when weighing it, as ants see it from the end of a cycle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EdgeContext {
+    pub from: usize,
+    pub to: usize,
+    pub distance: f64,
+    /// From `from` back to the depot.
+    pub distance_to_depot: f64,
+    /// From the depot out to `to`.
+    pub distance_from_depot: f64,
+    pub pheromone: f64,
+    pub exponents: Exponents,
+}
+
+/// How attractive an edge is to an ant choosing where to go next. Ants choose between edges with
+/// probability proportional to their weights.
+pub trait ConstructionHeuristic: Debug + Send + Sync {
+    /// The natural log of the edge's weight. Working in log space keeps weights finite however
+    /// large the savings or small the distances get.
+    fn edge_weight(&self, ctx: &EdgeContext) -> f64;
+}
+
+/// `savings^a * pheromone^b * (1 / distance)^c`, where the savings are how much shorter the edge
+/// is than going through the depot, as in the Clarke-Wright heuristic.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SavingsHeuristic;
+
+impl ConstructionHeuristic for SavingsHeuristic {
+    fn edge_weight(&self, ctx: &EdgeContext) -> f64 {
+        let savings = ctx.distance_to_depot + ctx.distance_from_depot - ctx.distance;
+        // Savings are negative when distances break the triangle inequality, which would
+        // corrupt the distribution, so treat such moves as barely worth taking
+        let savings = if savings < 0.0 { MIN_SAVINGS } else { savings };
+
+        let e = ctx.exponents.savings * savings.ln();
+        let p = ctx.exponents.pheromone * ctx.pheromone.ln();
+        let d = ctx.exponents.distance * ln_inverse_distance(ctx.distance);
+        e + p + d
+    }
+}
+
+/// `pheromone^b * (1 / distance)^c`, the classic Ant System weight, which ignores the savings.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DistanceHeuristic;
+
+impl ConstructionHeuristic for DistanceHeuristic {
+    fn edge_weight(&self, ctx: &EdgeContext) -> f64 {
+        ctx.exponents.pheromone * ctx.pheromone.ln()
+            + ctx.exponents.distance * ln_inverse_distance(ctx.distance)
+    }
+}
+
+/// `pheromone^alpha * (1 / distance)^beta`, the textbook Ant System transition rule, with its own
+/// exponents so that runs can be compared against the literature. These stay fixed, ignoring the
+/// configured [`Exponents`] and any adjustment to them while exploring.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AntSystemHeuristic {
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Default for AntSystemHeuristic {
+    /// The usual choice in the literature, weighing distance more than pheromone.
+    fn default() -> Self {
+        Self {
+            alpha: 1.0,
+            beta: 2.0,
+        }
+    }
+}
+
+impl ConstructionHeuristic for AntSystemHeuristic {
+    fn edge_weight(&self, ctx: &EdgeContext) -> f64 {
+        self.alpha * ctx.pheromone.ln() + self.beta * ln_inverse_distance(ctx.distance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heuristics() {
+        let ctx = EdgeContext {
+            from: 1,
+            to: 2,
+            distance: 2.0,
+            distance_to_depot: 3.0,
+            distance_from_depot: 3.0,
+            pheromone: 1.0,
+            exponents: Exponents {
+                savings: 2.0,
+                pheromone: 1.0,
+                distance: 1.0,
+            },
+        };
+
+        // 4^2 / 2 and 1 / 2
+        assert!((SavingsHeuristic.edge_weight(&ctx) - 8f64.ln()).abs() < 1e-12);
+        assert!((DistanceHeuristic.edge_weight(&ctx) - 0.5f64.ln()).abs() < 1e-12);
+
+        // Going through the depot would be shorter, which floors the savings
+        let detour = EdgeContext {
+            distance: 10.0,
+            ..ctx
+        };
+        assert!(SavingsHeuristic.edge_weight(&detour).is_finite());
+        assert!(SavingsHeuristic.edge_weight(&detour) < DistanceHeuristic.edge_weight(&detour));
+
+        // 4^1 * (1 / 2)^2, whatever the shared exponents
+        let ant_system = AntSystemHeuristic::default();
+        let strong_trail = EdgeContext {
+            pheromone: 4.0,
+            ..ctx
+        };
+        assert!((ant_system.edge_weight(&strong_trail) - 1f64.ln()).abs() < 1e-12);
+
+        // Colocated nodes are very attractive, but not infinitely so
+        let colocated = EdgeContext {
+            distance: 0.0,
+            ..ctx
+        };
+        assert!(SavingsHeuristic.edge_weight(&colocated).is_finite());
+        assert!(DistanceHeuristic.edge_weight(&colocated).is_finite());
+        assert!(ant_system.edge_weight(&colocated).is_finite());
+        assert!(DistanceHeuristic.edge_weight(&colocated) > DistanceHeuristic.edge_weight(&ctx));
+    }
+}
@@ -0,0 +1,145 @@
+use std::io::{Read, Write};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{cache::content_hash, config::SimulatorConfig, solution::Solution};
+
+/// What it takes to reproduce a set of runs, written alongside published results: the version
+/// and configuration that produced them, and what each instance came to.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub crate_version: String,
+    pub config: SimulatorConfig,
+    /// The heuristic and termination criterion as `{:?}` prints them. They can be any
+    /// implementation of their traits, so `config` leaves them out.
+    pub heuristic: String,
+    pub termination: String,
+    pub runs: Vec<ManifestRun>,
+}
+
+/// One instance's run within a [`Manifest`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ManifestRun {
+    pub instance: String,
+    /// A hash of the instance's text, to tell whether a file is the one that was solved. `None`
+    /// if the text wasn't available, e.g. when it was read from standard input.
+    pub instance_hash: Option<String>,
+    /// What the run seeded its random choices with, see [`Solution::seed`].
+    pub seed: Option<u64>,
+    pub cost: f64,
+    pub cycles: usize,
+    pub elapsed_secs: f64,
+}
+
+impl Manifest {
+    pub fn new(config: &SimulatorConfig) -> Self {
+        Self {
+            crate_version: String::from(env!("CARGO_PKG_VERSION")),
+            config: config.clone(),
+            heuristic: format!("{:?}", config.heuristic),
+            termination: format!("{:?}", config.termination),
+            runs: Vec::new(),
+        }
+    }
+
+    /// Records the run that found `solution`, on the instance whose text is `contents`.
+    pub fn add_run(&mut self, solution: &Solution, contents: Option<&str>) {
+        self.runs.push(ManifestRun {
+            instance: solution.name.clone(),
+            instance_hash: contents.map(|contents| format!("{:016x}", content_hash(contents))),
+            seed: solution.seed,
+            cost: solution.cost,
+            cycles: solution.cycles,
+            elapsed_secs: solution.elapsed.as_secs_f64(),
+        });
+    }
+
+    /// The config to repeat `run` with: the recorded one, seeded as the run was. The heuristic
+    /// and termination criterion are the defaults, so a run that used others needs them set
+    /// again, as [`Manifest::heuristic`] and [`Manifest::termination`] describe.
+    pub fn run_config(&self, run: &ManifestRun) -> SimulatorConfig {
+        SimulatorConfig {
+            seed: run.seed,
+            ..self.config.clone()
+        }
+    }
+
+    pub fn write_json<W: Write>(&self, w: W) -> Result<()> {
+        serde_json::to_writer_pretty(w, self)?;
+        Ok(())
+    }
+
+    pub fn read_json<R: Read>(reader: R) -> Result<Self> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::aco::{generate::generate_random, sim::Simulator};
+
+    #[test]
+    fn test_manifest_round_trips() -> Result<()> {
+        let solution = Solution {
+            name: String::from("manifest"),
+            route_costs: vec![10.5],
+            demands: vec![0.0, 1.0],
+            capacity: 1.0,
+            seed: Some(7),
+            cycles: 3,
+            elapsed: Duration::from_millis(1500),
+            ..Solution::for_test(vec![0, 1, 0], 10.5)
+        };
+        let config = SimulatorConfig {
+            deposit_q: 4.0,
+            ..SimulatorConfig::default()
+        };
+        let mut manifest = Manifest::new(&config);
+        manifest.add_run(&solution, Some("NAME : manifest\n"));
+        manifest.add_run(&solution, None);
+
+        let mut json = Vec::new();
+        manifest.write_json(&mut json)?;
+        let read = Manifest::read_json(&json[..])?;
+
+        assert_eq!(read.runs, manifest.runs);
+        assert_eq!(read.config.deposit_q, 4.0);
+        assert_eq!(read.termination, manifest.termination);
+        assert_eq!(read.runs[0].elapsed_secs, 1.5);
+        assert_eq!(read.runs[0].seed, Some(7));
+        assert_eq!(
+            read.runs[0].instance_hash.as_deref(),
+            Some("a5c7201921b287d5")
+        );
+        assert!(read.runs[1].instance_hash.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_manifest_reproduces_the_run() -> Result<()> {
+        let config = SimulatorConfig {
+            quiet: true,
+            ..SimulatorConfig::default()
+        };
+        let solution =
+            Simulator::with_config(generate_random(15, 100.0, 5)?, config.clone()).run()?;
+        let mut manifest = Manifest::new(&config);
+        manifest.add_run(&solution, None);
+
+        let mut json = Vec::new();
+        manifest.write_json(&mut json)?;
+        let read = Manifest::read_json(&json[..])?;
+        let config = read.run_config(&read.runs[0]);
+        let repeated = Simulator::with_config(generate_random(15, 100.0, 5)?, config).run()?;
+
+        assert_eq!(repeated.tour, solution.tour);
+        assert_eq!(repeated.cycles, solution.cycles);
+
+        Ok(())
+    }
+}
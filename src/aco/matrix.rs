@@ -0,0 +1,395 @@
+use std::io::{self, Write};
+
+use anyhow::{bail, Result};
+use yoos::collections::Matrix;
+
+/// How the distance between two coordinates is measured.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DistanceMetric {
+    Euclidean,
+    /// Euclidean rounded to the nearest integer, as TSPLIB defines EUC_2D.
+    RoundedEuclidean,
+    /// Sum of the differences in x and y rounded to the nearest integer, as TSPLIB defines
+    /// MAN_2D.
+    Manhattan,
+    /// Great circle distance in kilometres, with coordinates as `DDD.MM` latitude and
+    /// longitude, as TSPLIB defines GEO.
+    Geo,
+}
+
+impl DistanceMetric {
+    pub fn distance(self, (x1, y1): (f64, f64), (x2, y2): (f64, f64)) -> f64 {
+        match self {
+            DistanceMetric::Euclidean => (x1 - x2).hypot(y1 - y2),
+            DistanceMetric::RoundedEuclidean => (x1 - x2).hypot(y1 - y2).round(),
+            DistanceMetric::Manhattan => ((x1 - x2).abs() + (y1 - y2).abs()).round(),
+            DistanceMetric::Geo => {
+                // TSPLIB's truncated value, which published GEO distances depend on
+                #[allow(clippy::approx_constant)]
+                const PI: f64 = 3.141592;
+                const RRR: f64 = 6378.388;
+
+                // Degrees and minutes to radians
+                let radians = |v: f64| {
+                    let degrees = v.trunc();
+                    PI * (degrees + 5.0 * (v - degrees) / 3.0) / 180.0
+                };
+                let (lat1, long1, lat2, long2) =
+                    (radians(x1), radians(y1), radians(x2), radians(y2));
+
+                let q1 = (long1 - long2).cos();
+                let q2 = (lat1 - lat2).cos();
+                let q3 = (lat1 + lat2).cos();
+                (RRR * (0.5 * ((1.0 + q1) * q2 - (1.0 - q1) * q3)).acos() + 1.0).trunc()
+            }
+        }
+    }
+}
+
+/// Operations on [`Matrix`] that yoos doesn't provide, built on its row indexing.
+///
+/// New code reading single entries should prefer [`MatrixExt::get2`]. Indexing a row with
+/// `matrix[i][j]` stays for the hot loops, where taking the row once and indexing into it saves
+/// repeating the row lookup.
+pub trait MatrixExt {
+    /// The distance between every pair of `coordinates` under `metric`.
+    fn from_coordinates(coordinates: &[(f64, f64)], metric: DistanceMetric) -> Matrix;
+
+    /// The straight line distance between every pair of `(x, y, z)` points, as TSPLIB defines
+    /// EUC_3D.
+    fn from_coordinates_3d(points: &[(f64, f64, f64)]) -> Matrix;
+
+    /// The matrix with these rows, e.g. from an EXPLICIT instance or a CSV file. Unlike
+    /// `Matrix::new`, which only zero fills, this fails unless the rows make a square matrix
+    /// with zeroes along the diagonal, as a distance matrix must.
+    fn try_from_rows(rows: Vec<Vec<f64>>) -> Result<Matrix>;
+
+    /// An entry for entry copy, since `Matrix` doesn't implement `Clone`.
+    fn duplicate(&self) -> Matrix;
+
+    /// The entry in row `i` and column `j`.
+    ///
+    /// Panics, naming both indices, unless both are below the matrix's size.
+    fn get2(&self, i: usize, j: usize) -> f64;
+
+    fn row(&self, i: usize) -> &[f64];
+
+    fn column(&self, j: usize) -> Box<dyn Iterator<Item = f64> + '_>;
+
+    /// Every entry along with its row and column, row by row.
+    fn entries(&self) -> Box<dyn Iterator<Item = (usize, usize, f64)> + '_>;
+
+    /// Whether every entry is within `epsilon` of its mirror across the diagonal.
+    fn is_symmetric(&self, epsilon: f64) -> bool;
+
+    /// The node closest to node `i`, other than `i` itself, ties going to the lower node.
+    ///
+    /// Panics if there are no other nodes.
+    fn nearest(&self, i: usize) -> usize;
+
+    /// The `k` nodes closest to node `i`, other than `i` itself, closest first and ties going to
+    /// the lower node. All of them if there are fewer than `k`.
+    fn k_nearest(&self, i: usize, k: usize) -> Vec<usize>;
+
+    /// Writes the matrix as CSV, one line per row and no header.
+    fn write_csv<W: Write>(&self, w: W) -> io::Result<()>;
+
+    /// Multiplies every entry by `factor`.
+    fn scale(&mut self, factor: f64);
+
+    /// Replaces every entry with `f` of it.
+    fn map<F: Fn(f64) -> f64>(&mut self, f: F);
+}
+
+impl MatrixExt for Matrix {
+    fn from_coordinates(coordinates: &[(f64, f64)], metric: DistanceMetric) -> Matrix {
+        let mut matrix = Matrix::new(coordinates.len());
+        for (i, &from) in coordinates.iter().enumerate() {
+            for (j, &to) in coordinates.iter().enumerate() {
+                matrix[i][j] = metric.distance(from, to);
+            }
+        }
+        matrix
+    }
+
+    fn from_coordinates_3d(points: &[(f64, f64, f64)]) -> Matrix {
+        let mut matrix = Matrix::new(points.len());
+        for (i, &(x1, y1, z1)) in points.iter().enumerate() {
+            for (j, &(x2, y2, z2)) in points.iter().enumerate() {
+                matrix[i][j] = (x1 - x2).hypot(y1 - y2).hypot(z1 - z2);
+            }
+        }
+        matrix
+    }
+
+    fn try_from_rows(rows: Vec<Vec<f64>>) -> Result<Matrix> {
+        let n = rows.len();
+        let mut matrix = Matrix::new(n);
+        for (i, row) in rows.iter().enumerate() {
+            if row.len() != n {
+                bail!(
+                    "Row {} has {} entries, but there are {} rows",
+                    i + 1,
+                    row.len(),
+                    n
+                );
+            }
+            if row[i] != 0.0 {
+                bail!("Row {} is {} from itself, but should be 0", i + 1, row[i]);
+            }
+            matrix[i].copy_from_slice(row);
+        }
+        Ok(matrix)
+    }
+
+    fn duplicate(&self) -> Matrix {
+        let mut copy = Matrix::new(self.size());
+        for i in 0..self.size() {
+            copy[i].copy_from_slice(&self[i]);
+        }
+        copy
+    }
+
+    fn get2(&self, i: usize, j: usize) -> f64 {
+        let n = self.size();
+        assert!(
+            i < n && j < n,
+            "index ({}, {}) out of bounds for a {}x{} matrix",
+            i,
+            j,
+            n,
+            n
+        );
+        self[i][j]
+    }
+
+    fn row(&self, i: usize) -> &[f64] {
+        &self[i]
+    }
+
+    fn column(&self, j: usize) -> Box<dyn Iterator<Item = f64> + '_> {
+        Box::new((0..self.size()).map(move |i| self[i][j]))
+    }
+
+    fn entries(&self) -> Box<dyn Iterator<Item = (usize, usize, f64)> + '_> {
+        Box::new(
+            (0..self.size())
+                .flat_map(move |i| self[i].iter().enumerate().map(move |(j, &v)| (i, j, v))),
+        )
+    }
+
+    fn is_symmetric(&self, epsilon: f64) -> bool {
+        (0..self.size())
+            .all(|i| (i + 1..self.size()).all(|j| (self[i][j] - self[j][i]).abs() <= epsilon))
+    }
+
+    fn nearest(&self, i: usize) -> usize {
+        (0..self.size())
+            .filter(|&j| j != i)
+            .min_by(|&a, &b| self[i][a].total_cmp(&self[i][b]))
+            .unwrap()
+    }
+
+    fn k_nearest(&self, i: usize, k: usize) -> Vec<usize> {
+        let mut neighbours: Vec<usize> = (0..self.size()).filter(|&j| j != i).collect();
+        neighbours.sort_by(|&a, &b| self[i][a].total_cmp(&self[i][b]).then(a.cmp(&b)));
+        neighbours.truncate(k);
+        neighbours
+    }
+
+    fn write_csv<W: Write>(&self, mut w: W) -> io::Result<()> {
+        for i in 0..self.size() {
+            let row: Vec<String> = self[i].iter().map(f64::to_string).collect();
+            writeln!(w, "{}", row.join(","))?;
+        }
+        Ok(())
+    }
+
+    fn scale(&mut self, factor: f64) {
+        self.map(|v| v * factor);
+    }
+
+    fn map<F: Fn(f64) -> f64>(&mut self, f: F) {
+        for i in 0..self.size() {
+            for v in self[i].iter_mut() {
+                *v = f(*v);
+            }
+        }
+    }
+}
+
+/// (De)serializes a [`Matrix`] as a list of its rows, since yoos doesn't implement serde. For
+/// fields holding one, with `#[serde(with = "rows")]`.
+pub(crate) mod rows {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use yoos::collections::Matrix;
+
+    pub fn serialize<S: Serializer>(matrix: &Matrix, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq((0..matrix.size()).map(|i| &matrix[i]))
+    }
+
+    /// Fails unless the rows make a square matrix.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Matrix, D::Error> {
+        let rows = Vec::<Vec<f64>>::deserialize(deserializer)?;
+        let n = rows.len();
+        if let Some(row) = rows.iter().find(|row| row.len() != n) {
+            return Err(D::Error::custom(format!(
+                "Expected {} entries in every row, got {}",
+                n,
+                row.len()
+            )));
+        }
+
+        let mut matrix = Matrix::new(n);
+        for (i, row) in rows.iter().enumerate() {
+            matrix[i].copy_from_slice(row);
+        }
+        Ok(matrix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aco::{builder::ProblemBuilder, savings::clarke_wright};
+
+    fn counting_matrix(n: usize) -> Matrix {
+        let mut matrix = Matrix::new(n);
+        for i in 0..n {
+            for j in 0..n {
+                matrix[i][j] = (i * n + j) as f64;
+            }
+        }
+        matrix
+    }
+
+    #[test]
+    fn test_rows_columns_and_entries() {
+        let matrix = counting_matrix(3);
+
+        assert_eq!(matrix.row(1), &[3.0, 4.0, 5.0]);
+        assert_eq!(matrix.column(1).collect::<Vec<_>>(), vec![1.0, 4.0, 7.0]);
+        assert_eq!(matrix.entries().count(), 9);
+        assert_eq!(matrix.entries().nth(5), Some((1, 2, 5.0)));
+    }
+
+    #[test]
+    fn test_get2() {
+        let matrix = counting_matrix(3);
+        assert_eq!(matrix.get2(1, 2), 5.0);
+        assert_eq!(matrix.get2(2, 0), 6.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "index (0, 3) out of bounds for a 3x3 matrix")]
+    fn test_get2_out_of_bounds() {
+        // matrix[0][3] would panic too, but only as a slice index, without the row
+        counting_matrix(3).get2(0, 3);
+    }
+
+    #[test]
+    fn test_try_from_rows() -> Result<()> {
+        let matrix = Matrix::try_from_rows(vec![vec![0.0, 2.0], vec![3.0, 0.0]])?;
+        assert_eq!(matrix.row(1), &[3.0, 0.0]);
+
+        let ragged = Matrix::try_from_rows(vec![vec![0.0, 2.0], vec![3.0]]);
+        assert!(ragged
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("Row 2 has 1 entries"));
+
+        let diagonal = Matrix::try_from_rows(vec![vec![0.0, 2.0], vec![3.0, 1.0]]);
+        assert!(diagonal.err().unwrap().to_string().contains("should be 0"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_coordinates() {
+        let coordinates = [(0.0, 0.0), (3.0, 4.0), (1.0, 1.0)];
+
+        let euclidean = Matrix::from_coordinates(&coordinates, DistanceMetric::Euclidean);
+        assert_eq!(euclidean[0][1], 5.0);
+        assert_eq!(euclidean[1][0], 5.0);
+        assert_eq!(euclidean[0][2], 2.0f64.sqrt());
+
+        let rounded = Matrix::from_coordinates(&coordinates, DistanceMetric::RoundedEuclidean);
+        assert_eq!(rounded[0][2], 1.0);
+        assert_eq!(rounded[2][2], 0.0);
+    }
+
+    #[test]
+    fn test_is_symmetric() {
+        let mut matrix = Matrix::from_coordinates(
+            &[(0.0, 0.0), (3.0, 4.0), (6.0, 0.0)],
+            DistanceMetric::Euclidean,
+        );
+        assert!(matrix.is_symmetric(0.0));
+
+        matrix[0][2] += 1e-12;
+        assert!(!matrix.is_symmetric(0.0));
+        assert!(matrix.is_symmetric(1e-9));
+    }
+
+    #[test]
+    fn test_nearest() {
+        let matrix = Matrix::from_coordinates(
+            &[(0.0, 0.0), (5.0, 0.0), (0.0, 3.0), (-3.0, 0.0), (1.0, 1.0)],
+            DistanceMetric::Euclidean,
+        );
+
+        assert_eq!(matrix.nearest(0), 4);
+        assert_eq!(matrix.nearest(4), 0);
+        // Nodes 2 and 3 are both 3 away, so the lower wins
+        assert_eq!(matrix.k_nearest(0, 3), vec![4, 2, 3]);
+        assert_eq!(matrix.k_nearest(1, 10), vec![4, 0, 2, 3]);
+    }
+
+    #[test]
+    fn test_write_csv() -> io::Result<()> {
+        let mut csv = Vec::new();
+        counting_matrix(2).write_csv(&mut csv)?;
+
+        assert_eq!(String::from_utf8(csv).unwrap(), "0,1\n2,3\n");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_map() {
+        let mut matrix = counting_matrix(2);
+        matrix.map(|v| v * v + 1.0);
+
+        assert_eq!(
+            matrix.entries().map(|(_, _, v)| v).collect::<Vec<_>>(),
+            [1.0, 2.0, 5.0, 10.0]
+        );
+    }
+
+    #[test]
+    fn test_scale_invariance() -> anyhow::Result<()> {
+        let mut problem = ProblemBuilder::new()
+            .capacity(10.0)
+            .coordinates(vec![
+                (0.0, 0.0),
+                (1.0, 7.0),
+                (4.0, 2.0),
+                (9.0, 9.0),
+                (3.0, 11.0),
+                (8.0, 1.5),
+                (6.0, 6.0),
+            ])
+            .demands(vec![0.0, 4.0, 3.0, 5.0, 2.0, 6.0, 3.0])
+            .build()?;
+        let unscaled = clarke_wright(&problem);
+
+        problem.adjacency_matrix.scale(1000.0);
+        let scaled = clarke_wright(&problem);
+
+        assert_eq!(scaled.tour, unscaled.tour);
+        assert!((scaled.cost - 1000.0 * unscaled.cost).abs() < 1e-6);
+
+        Ok(())
+    }
+}
@@ -1,8 +1,50 @@
 mod ant;
+mod builder;
+mod cache;
+mod candidates;
+mod config;
+mod generate;
+mod heuristic;
+mod manifest;
+mod matrix;
 mod opt;
 mod problem;
+#[cfg(feature = "profiling")]
+mod profiling;
+mod savings;
+mod schedule;
 mod sim;
+mod solution;
+mod termination;
 mod utils;
 
-pub use problem::Problem;
-pub use sim::Simulator;
+pub use ant::Exponents;
+pub use builder::ProblemBuilder;
+pub use cache::read_cached;
+pub use candidates::{build_candidate_lists, candidate_lists_from_matrix};
+pub use config::{
+    CostFormat, DepotReturn, Evaporation, ExponentSchedule, LocalSearchAnts, SimulatorConfig,
+    StagnationPolicy,
+};
+pub use generate::generate_random;
+pub use heuristic::{
+    AntSystemHeuristic, ConstructionHeuristic, DistanceHeuristic, EdgeContext, SavingsHeuristic,
+};
+pub use manifest::{Manifest, ManifestRun};
+pub use matrix::{DistanceMetric, MatrixExt};
+pub use opt::{
+    Improvement, InterRouteOrOptStrategy, LocalSearch, NoOpStrategy, OptimizationStrategy,
+    OrOptStrategy, TwoHalfOptStrategy, TwoOptStrategy,
+};
+pub use problem::{EdgeWeightType, Problem, ProblemType};
+#[cfg(feature = "profiling")]
+pub use profiling::Timings;
+pub use savings::clarke_wright;
+pub use schedule::{schedule, RouteSchedule, Stop};
+pub use sim::{solve, Simulator, SimulatorState, StepResult};
+pub use solution::{ObjectiveWeights, Objectives, Solution, SolutionDiff};
+pub use termination::{
+    Any, Interrupted, MaxCycles, OptimumReached, RunContext, Stagnation, TerminationCriterion,
+    TimeBudget,
+};
+pub use utils::{format_cost, path_to_routes, tour_cost, validate_route_distances, validate_tour};
@@ -1,5 +1,11 @@
+use serde::{Deserialize, Serialize};
 use yoos::collections::Matrix;
 
+use super::utils::route_load;
+
+/// Gains smaller than this are treated as floating point noise rather than improvements.
+const DEFAULT_MIN_GAIN: f64 = 1e-9;
+
 pub trait OptimizationStrategy {
     fn convert_to_multiple_paths(path: &[usize]) -> Vec<Vec<usize>> {
         let mut paths = crate::aco::utils::path_to_routes(path);
@@ -9,7 +15,10 @@ pub trait OptimizationStrategy {
             path.insert(0, 0);
         }
 
-        paths.remove(paths.len() - 1);
+        // A tour that never leaves the depot still has to start and end there
+        if paths.is_empty() {
+            paths.push(vec![0, 0]);
+        }
 
         paths
     }
@@ -19,31 +28,74 @@ pub trait OptimizationStrategy {
     }
 
     fn calc_path_length(path: &[usize], adjacency_matrix: &Matrix) -> f64 {
-        path.iter()
-            .zip(path.iter().skip(1))
-            .fold(0.0, |a, (&i, &j)| a + adjacency_matrix[i][j])
+        crate::aco::utils::tour_cost(path, adjacency_matrix)
     }
 
     fn optimize(&self, path: &[usize], adjacency_matrix: &Matrix) -> (Vec<usize>, f64);
 }
 
-pub struct TwoOptStrategy;
+/// Which improving move a local search applies when there are several.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Improvement {
+    /// The first one found, which is cheap but can settle in a worse local optimum.
+    First,
+    /// The one gaining the most, which takes a scan of every move each time.
+    Best,
+}
+
+pub struct TwoOptStrategy {
+    /// How much shorter a swap must make the route to be applied.
+    pub min_gain: f64,
+    pub improvement: Improvement,
+    /// The most swaps to apply to a single route before settling for it as is, keeping the cost
+    /// of optimizing very long routes bounded. `None` keeps going until no swap improves it.
+    pub max_iterations: Option<usize>,
+}
+
+impl Default for TwoOptStrategy {
+    fn default() -> Self {
+        Self {
+            min_gain: DEFAULT_MIN_GAIN,
+            improvement: Improvement::First,
+            max_iterations: None,
+        }
+    }
+}
 
 impl TwoOptStrategy {
-    fn optimize_path(path: Vec<usize>, adjacency_matrix: &Matrix) -> Vec<usize> {
-        for i in 0..path.len() - 2 {
+    fn optimize_path(&self, mut path: Vec<usize>, adjacency_matrix: &Matrix) -> Vec<usize> {
+        for _ in 0..self.max_iterations.unwrap_or(usize::MAX) {
+            match self.improving_swap(&path, adjacency_matrix) {
+                Some((i, k)) => path = Self::swap(&path, i, k),
+                None => break,
+            }
+        }
+        path
+    }
+
+    /// A reversal of `path[i + 1..=k]` that gains more than `min_gain`, as `(i, k)`, either the
+    /// first or the best depending on `improvement`.
+    fn improving_swap(&self, path: &[usize], adjacency_matrix: &Matrix) -> Option<(usize, usize)> {
+        let mut best = None;
+        let mut best_gain = self.min_gain;
+        for i in 0..path.len().saturating_sub(2) {
             for k in i + 1..path.len() - 1 {
                 let removed_edge_cost =
                     adjacency_matrix[path[i]][path[i + 1]] + adjacency_matrix[path[k]][path[k + 1]];
                 let new_edges_cost =
                     adjacency_matrix[path[i]][path[k]] + adjacency_matrix[path[i + 1]][path[k + 1]];
 
-                if removed_edge_cost - new_edges_cost > 1.0 {
-                    return Self::optimize_path(Self::swap(&path, i, k), adjacency_matrix);
+                let gain = removed_edge_cost - new_edges_cost;
+                if gain > best_gain {
+                    if self.improvement == Improvement::First {
+                        return Some((i, k));
+                    }
+                    best = Some((i, k));
+                    best_gain = gain;
                 }
             }
         }
-        path
+        best
     }
 
     fn swap(path: &[usize], i: usize, k: usize) -> Vec<usize> {
@@ -76,7 +128,7 @@ impl OptimizationStrategy for TwoOptStrategy {
         let new_path = Self::convert_to_single_path(
             paths
                 .into_iter()
-                .map(|p| Self::optimize_path(p, adjacency_matrix))
+                .map(|p| self.optimize_path(p, adjacency_matrix))
                 .collect(),
         );
 
@@ -87,6 +139,310 @@ impl OptimizationStrategy for TwoOptStrategy {
     }
 }
 
+/// 2-opt extended with single node relocation, sometimes called 2.5-opt. Each pass finds the best
+/// segment reversal and the best move of one node elsewhere in the route, and applies whichever
+/// improves the route more.
+pub struct TwoHalfOptStrategy {
+    /// How much shorter a move must make the route to be applied.
+    pub min_gain: f64,
+}
+
+impl Default for TwoHalfOptStrategy {
+    fn default() -> Self {
+        Self {
+            min_gain: DEFAULT_MIN_GAIN,
+        }
+    }
+}
+
+impl TwoHalfOptStrategy {
+    fn optimize_path(&self, mut path: Vec<usize>, adjacency_matrix: &Matrix) -> Vec<usize> {
+        loop {
+            let (reversal_gain, i, k) = Self::best_reversal(&path, adjacency_matrix);
+            let (relocation_gain, from, after) = Self::best_relocation(&path, adjacency_matrix);
+
+            if reversal_gain.max(relocation_gain) <= self.min_gain {
+                return path;
+            }
+
+            path = if reversal_gain >= relocation_gain {
+                TwoOptStrategy::swap(&path, i, k)
+            } else {
+                Self::relocate(&path, from, after)
+            };
+        }
+    }
+
+    /// The most improving reversal of `path[i + 1..=k]`, as `(gain, i, k)`.
+    fn best_reversal(path: &[usize], adjacency_matrix: &Matrix) -> (f64, usize, usize) {
+        let mut best = (0.0, 0, 0);
+        for i in 0..path.len().saturating_sub(2) {
+            for k in i + 1..path.len() - 1 {
+                let removed_edge_cost =
+                    adjacency_matrix[path[i]][path[i + 1]] + adjacency_matrix[path[k]][path[k + 1]];
+                let new_edges_cost =
+                    adjacency_matrix[path[i]][path[k]] + adjacency_matrix[path[i + 1]][path[k + 1]];
+
+                let gain = removed_edge_cost - new_edges_cost;
+                if gain > best.0 {
+                    best = (gain, i, k);
+                }
+            }
+        }
+        best
+    }
+
+    /// The most improving move of `path[from]` to just after `path[after]`, as
+    /// `(gain, from, after)`. The depots at either end are never moved.
+    fn best_relocation(path: &[usize], adjacency_matrix: &Matrix) -> (f64, usize, usize) {
+        let mut best = (0.0, 0, 0);
+        for from in 1..path.len().saturating_sub(1) {
+            let (prev, node, next) = (path[from - 1], path[from], path[from + 1]);
+            let removal_gain = adjacency_matrix[prev][node] + adjacency_matrix[node][next]
+                - adjacency_matrix[prev][next];
+
+            for after in 0..path.len() - 1 {
+                // Inserting next to its current neighbours leaves the route unchanged
+                if after == from - 1 || after == from {
+                    continue;
+                }
+
+                let (u, v) = (path[after], path[after + 1]);
+                let insertion_cost =
+                    adjacency_matrix[u][node] + adjacency_matrix[node][v] - adjacency_matrix[u][v];
+
+                let gain = removal_gain - insertion_cost;
+                if gain > best.0 {
+                    best = (gain, from, after);
+                }
+            }
+        }
+        best
+    }
+
+    fn relocate(path: &[usize], from: usize, after: usize) -> Vec<usize> {
+        let mut relocated = path.to_vec();
+        let node = relocated.remove(from);
+        // Removing the node shifts everything after it one to the left
+        let insert_at = if after < from { after + 1 } else { after };
+        relocated.insert(insert_at, node);
+        relocated
+    }
+}
+
+impl OptimizationStrategy for TwoHalfOptStrategy {
+    fn optimize(&self, path: &[usize], adjacency_matrix: &Matrix) -> (Vec<usize>, f64) {
+        let paths = Self::convert_to_multiple_paths(path);
+
+        let new_path = Self::convert_to_single_path(
+            paths
+                .into_iter()
+                .map(|p| self.optimize_path(p, adjacency_matrix))
+                .collect(),
+        );
+
+        let length = Self::calc_path_length(&new_path, adjacency_matrix);
+
+        (new_path, length)
+    }
+}
+
+/// Or-opt: moves segments of up to `max_segment` consecutive customers elsewhere in their route,
+/// keeping their order. Each pass applies the single most improving move.
+pub struct OrOptStrategy {
+    /// How much shorter a move must make the route to be applied.
+    pub min_gain: f64,
+    /// The longest segment considered for moving.
+    pub max_segment: usize,
+}
+
+impl Default for OrOptStrategy {
+    fn default() -> Self {
+        Self {
+            min_gain: DEFAULT_MIN_GAIN,
+            max_segment: 3,
+        }
+    }
+}
+
+impl OrOptStrategy {
+    fn optimize_path(&self, mut path: Vec<usize>, adjacency_matrix: &Matrix) -> Vec<usize> {
+        loop {
+            let (gain, from, len, after) = self.best_move(&path, adjacency_matrix);
+            if gain <= self.min_gain {
+                return path;
+            }
+            path = Self::move_segment(&path, from, len, after);
+        }
+    }
+
+    /// The most improving move of `path[from..from + len]` to just after `path[after]`, as
+    /// `(gain, from, len, after)`. The depots at either end are never moved.
+    fn best_move(&self, path: &[usize], adjacency_matrix: &Matrix) -> (f64, usize, usize, usize) {
+        let m = adjacency_matrix;
+        let mut best = (0.0, 0, 0, 0);
+        for len in 1..=self.max_segment {
+            for from in 1..path.len().saturating_sub(len) {
+                let (first, last) = (path[from], path[from + len - 1]);
+                let (prev, next) = (path[from - 1], path[from + len]);
+                let removal_gain = m[prev][first] + m[last][next] - m[prev][next];
+
+                for after in 0..path.len() - 1 {
+                    // Inserting inside or right next to the segment leaves the route unchanged
+                    if after + 1 >= from && after < from + len {
+                        continue;
+                    }
+
+                    let (u, v) = (path[after], path[after + 1]);
+                    let insertion_cost = m[u][first] + m[last][v] - m[u][v];
+
+                    let gain = removal_gain - insertion_cost;
+                    if gain > best.0 {
+                        best = (gain, from, len, after);
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    fn move_segment(path: &[usize], from: usize, len: usize, after: usize) -> Vec<usize> {
+        let mut moved = path.to_vec();
+        let segment: Vec<usize> = moved.drain(from..from + len).collect();
+        // Removing the segment shifts everything after it left by its length
+        let insert_at = if after < from {
+            after + 1
+        } else {
+            after + 1 - len
+        };
+        moved.splice(insert_at..insert_at, segment);
+        moved
+    }
+}
+
+impl OptimizationStrategy for OrOptStrategy {
+    fn optimize(&self, path: &[usize], adjacency_matrix: &Matrix) -> (Vec<usize>, f64) {
+        let paths = Self::convert_to_multiple_paths(path);
+
+        let new_path = Self::convert_to_single_path(
+            paths
+                .into_iter()
+                .map(|p| self.optimize_path(p, adjacency_matrix))
+                .collect(),
+        );
+
+        let length = Self::calc_path_length(&new_path, adjacency_matrix);
+
+        (new_path, length)
+    }
+}
+
+/// Or-opt between routes: moves segments of up to `max_segment` consecutive customers from one
+/// route into another with room for them, keeping their order. Each pass applies the single most
+/// improving move, and routes left empty are dropped. Unlike the other strategies this has to
+/// know the demands, so it is built per instance rather than chosen through [`LocalSearch`].
+pub struct InterRouteOrOptStrategy<'a> {
+    pub demands: &'a [f64],
+    pub capacity: f64,
+    /// The furthest any one route may drive, if limited.
+    pub max_route_distance: Option<f64>,
+    /// How much shorter a move must make the tour to be applied.
+    pub min_gain: f64,
+    /// The longest segment considered for moving.
+    pub max_segment: usize,
+}
+
+impl<'a> InterRouteOrOptStrategy<'a> {
+    pub fn new(demands: &'a [f64], capacity: f64) -> Self {
+        Self {
+            demands,
+            capacity,
+            max_route_distance: None,
+            min_gain: DEFAULT_MIN_GAIN,
+            max_segment: 3,
+        }
+    }
+
+    /// The most improving move of `routes[r][from..from + len]` to just after `routes[s][after]`,
+    /// as `(gain, r, from, len, s, after)`, among those that keep route `s` within the capacity
+    /// and the distance limit.
+    fn best_move(
+        &self,
+        routes: &[Vec<usize>],
+        adjacency_matrix: &Matrix,
+    ) -> (f64, usize, usize, usize, usize, usize) {
+        let m = adjacency_matrix;
+        let loads: Vec<f64> = routes
+            .iter()
+            .map(|route| route_load(route, self.demands))
+            .collect();
+        let lengths: Vec<f64> = routes
+            .iter()
+            .map(|route| Self::calc_path_length(route, m))
+            .collect();
+
+        let mut best = (0.0, 0, 0, 0, 0, 0);
+        for (r, route) in routes.iter().enumerate() {
+            for len in 1..=self.max_segment {
+                for from in 1..route.len().saturating_sub(len) {
+                    let segment = &route[from..from + len];
+                    let demand = route_load(segment, self.demands);
+                    let inside = Self::calc_path_length(segment, m);
+                    let (first, last) = (segment[0], segment[len - 1]);
+                    let (prev, next) = (route[from - 1], route[from + len]);
+                    let removal_gain = m[prev][first] + m[last][next] - m[prev][next];
+
+                    for (s, target) in routes.iter().enumerate() {
+                        if s == r || loads[s] + demand > self.capacity {
+                            continue;
+                        }
+
+                        for after in 0..target.len() - 1 {
+                            let (u, v) = (target[after], target[after + 1]);
+                            let insertion_cost = m[u][first] + m[last][v] - m[u][v];
+                            let too_far = matches!(
+                                self.max_route_distance,
+                                Some(max) if lengths[s] + insertion_cost + inside > max
+                            );
+
+                            let gain = removal_gain - insertion_cost;
+                            if gain > best.0 && !too_far {
+                                best = (gain, r, from, len, s, after);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        best
+    }
+}
+
+impl OptimizationStrategy for InterRouteOrOptStrategy<'_> {
+    fn optimize(&self, path: &[usize], adjacency_matrix: &Matrix) -> (Vec<usize>, f64) {
+        let mut routes = Self::convert_to_multiple_paths(path);
+
+        loop {
+            let (gain, r, from, len, s, after) = self.best_move(&routes, adjacency_matrix);
+            if gain <= self.min_gain {
+                break;
+            }
+            let segment: Vec<usize> = routes[r].drain(from..from + len).collect();
+            routes[s].splice(after + 1..after + 1, segment);
+        }
+
+        // Moving every customer out of a route leaves just the depot twice
+        if routes.len() > 1 {
+            routes.retain(|route| route.len() > 2);
+        }
+
+        let new_path = Self::convert_to_single_path(routes);
+        let length = Self::calc_path_length(&new_path, adjacency_matrix);
+
+        (new_path, length)
+    }
+}
+
 pub struct NoOpStrategy;
 
 impl OptimizationStrategy for NoOpStrategy {
@@ -97,3 +453,214 @@ impl OptimizationStrategy for NoOpStrategy {
         )
     }
 }
+
+/// Which local search ants run on their tours, each with its default settings.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LocalSearch {
+    /// Leave tours as the ants built them.
+    None,
+    TwoOpt,
+    TwoHalfOpt,
+    OrOpt,
+    /// Each search in turn, every one starting from the tour the last left.
+    Composite(Vec<LocalSearch>),
+}
+
+impl OptimizationStrategy for LocalSearch {
+    fn optimize(&self, path: &[usize], adjacency_matrix: &Matrix) -> (Vec<usize>, f64) {
+        match self {
+            LocalSearch::None => NoOpStrategy.optimize(path, adjacency_matrix),
+            LocalSearch::TwoOpt => TwoOptStrategy::default().optimize(path, adjacency_matrix),
+            LocalSearch::TwoHalfOpt => {
+                TwoHalfOptStrategy::default().optimize(path, adjacency_matrix)
+            }
+            LocalSearch::OrOpt => OrOptStrategy::default().optimize(path, adjacency_matrix),
+            LocalSearch::Composite(searches) => searches.iter().fold(
+                NoOpStrategy.optimize(path, adjacency_matrix),
+                |(path, _), search| search.optimize(&path, adjacency_matrix),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+    use crate::aco::{problem::Problem, utils::path_to_routes};
+
+    fn euclidean(coordinates: &[(f64, f64)]) -> Matrix {
+        let mut matrix = Matrix::new(coordinates.len());
+        for (i, &(x1, y1)) in coordinates.iter().enumerate() {
+            for (j, &(x2, y2)) in coordinates.iter().enumerate() {
+                matrix[i][j] = (x1 - x2).hypot(y1 - y2);
+            }
+        }
+        matrix
+    }
+
+    #[test]
+    fn test_two_half_opt_beats_two_opt() {
+        let adjacency_matrix = euclidean(&[
+            (0.0, 19.0),
+            (14.0, 11.0),
+            (11.0, 19.0),
+            (14.0, 6.0),
+            (9.0, 0.0),
+            (12.0, 13.0),
+        ]);
+        let path = [0, 1, 2, 3, 4, 5, 0];
+
+        let (_, two_opt_cost) = TwoOptStrategy::default().optimize(&path, &adjacency_matrix);
+        let (_, two_half_opt_cost) =
+            TwoHalfOptStrategy::default().optimize(&path, &adjacency_matrix);
+
+        assert!(two_half_opt_cost < two_opt_cost);
+    }
+
+    #[test]
+    fn test_two_half_opt_beats_two_opt_on_a_n32_k5() -> anyhow::Result<()> {
+        let problem = Problem::try_from_vrp(File::open("./inputs/A-n32-k5.vrp")?)?;
+        let customers = problem.demands().len() - 1;
+
+        // Starting from tours visiting every k-th customer in turn, for every k, each route
+        // ending when the next customer doesn't fit
+        let (mut two_opt_total, mut two_half_opt_total) = (0.0, 0.0);
+        for k in 1..customers {
+            let mut path = vec![0];
+            let mut load = 0.0;
+            for i in 1..=customers {
+                let customer = i * k % customers + 1;
+                load += problem.demands()[customer];
+                if load > problem.capacity {
+                    path.push(0);
+                    load = problem.demands()[customer];
+                }
+                path.push(customer);
+            }
+            path.push(0);
+
+            two_opt_total += TwoOptStrategy::default()
+                .optimize(&path, problem.matrix())
+                .1;
+            two_half_opt_total += TwoHalfOptStrategy::default()
+                .optimize(&path, problem.matrix())
+                .1;
+        }
+
+        assert!(two_half_opt_total < two_opt_total);
+        Ok(())
+    }
+
+    #[test]
+    fn test_two_opt_improves_small_scale_routes() {
+        // A route crossing itself, scaled down so that uncrossing it gains less than 1.0
+        let adjacency_matrix = euclidean(&[(0.0, 0.0), (0.01, 0.01), (0.01, 0.0), (0.0, 0.01)]);
+        let path = [0, 1, 2, 3, 0];
+        let cost = TwoOptStrategy::calc_path_length(&path, &adjacency_matrix);
+
+        let (_, unit_threshold_cost) = TwoOptStrategy {
+            min_gain: 1.0,
+            ..TwoOptStrategy::default()
+        }
+        .optimize(&path, &adjacency_matrix);
+        let (_, default_cost) = TwoOptStrategy::default().optimize(&path, &adjacency_matrix);
+
+        assert_eq!(unit_threshold_cost, cost);
+        assert!(default_cost < cost);
+    }
+
+    #[test]
+    fn test_best_improvement_beats_first() {
+        let adjacency_matrix = euclidean(&[
+            (6.0, 3.0),
+            (7.0, 6.0),
+            (4.0, 7.0),
+            (18.0, 10.0),
+            (6.0, 18.0),
+            (1.0, 4.0),
+        ]);
+        let path = [0, 1, 2, 3, 4, 5, 0];
+
+        let (_, first_cost) = TwoOptStrategy::default().optimize(&path, &adjacency_matrix);
+        let (_, best_cost) = TwoOptStrategy {
+            improvement: Improvement::Best,
+            ..TwoOptStrategy::default()
+        }
+        .optimize(&path, &adjacency_matrix);
+
+        assert!(best_cost < first_cost);
+    }
+
+    #[test]
+    fn test_two_opt_max_iterations() {
+        let adjacency_matrix = euclidean(&[(0.0, 0.0), (1.0, 1.0), (1.0, 0.0), (0.0, 1.0)]);
+        let path = [0, 1, 2, 3, 0];
+
+        let capped = TwoOptStrategy {
+            max_iterations: Some(0),
+            ..TwoOptStrategy::default()
+        };
+
+        assert_eq!(capped.optimize(&path, &adjacency_matrix).0, path);
+        assert_ne!(
+            TwoOptStrategy::default()
+                .optimize(&path, &adjacency_matrix)
+                .0,
+            path
+        );
+    }
+
+    #[test]
+    fn test_or_opt_moves_segments() {
+        // 3 and 4 sit between 1 and 2 on the way out, but belong after 2 on the way back
+        let adjacency_matrix =
+            euclidean(&[(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.0, 1.0)]);
+        let path = [0, 3, 4, 1, 2, 0];
+
+        let (new_path, cost) = OrOptStrategy::default().optimize(&path, &adjacency_matrix);
+
+        assert!(cost < OrOptStrategy::calc_path_length(&path, &adjacency_matrix));
+        assert_eq!(new_path, [0, 1, 2, 3, 4, 0]);
+    }
+
+    #[test]
+    fn test_inter_route_or_opt() {
+        // 3 is served by the route out east, but sits right next to 4 up north
+        let adjacency_matrix = euclidean(&[
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (11.0, 0.0),
+            (0.0, 10.0),
+            (0.0, 11.0),
+        ]);
+        let path = [0, 1, 2, 3, 0, 4, 0];
+        let demands = [0.0, 1.0, 1.0, 1.0, 1.0];
+
+        let (new_path, cost) =
+            InterRouteOrOptStrategy::new(&demands, 2.0).optimize(&path, &adjacency_matrix);
+        assert!(cost < TwoOptStrategy::calc_path_length(&path, &adjacency_matrix));
+        assert_eq!(path_to_routes(&new_path), vec![vec![1, 2], vec![3, 4]]);
+
+        // Without room for it, 3 has to stay where it is
+        let (new_path, _) =
+            InterRouteOrOptStrategy::new(&demands, 1.0).optimize(&path, &adjacency_matrix);
+        assert_eq!(path_to_routes(&new_path), vec![vec![1, 2, 3], vec![4]]);
+    }
+
+    #[test]
+    fn test_local_search_none_leaves_path() {
+        let adjacency_matrix = euclidean(&[(0.0, 0.0), (1.0, 1.0), (1.0, 0.0), (0.0, 1.0)]);
+        let path = [0, 1, 2, 3, 0];
+
+        let (none, _) = LocalSearch::None.optimize(&path, &adjacency_matrix);
+        let (composite, composite_cost) =
+            LocalSearch::Composite(vec![LocalSearch::TwoOpt, LocalSearch::OrOpt])
+                .optimize(&path, &adjacency_matrix);
+
+        assert_eq!(none, path);
+        assert!(composite_cost < TwoOptStrategy::calc_path_length(&path, &adjacency_matrix));
+        assert_eq!(composite.len(), path.len());
+    }
+}
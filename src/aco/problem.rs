@@ -1,14 +1,19 @@
 use std::{
+    fmt,
     fs::File,
-    io::Read,
-    num::ParseFloatError,
+    io::{self, Read, Write},
     str::FromStr,
 };
 
 use anyhow::{anyhow, Result};
-use strum::EnumString;
+use strum::{Display, EnumString};
 use yoos::collections::Matrix;
 
+use super::{
+    builder::ProblemBuilder,
+    matrix::{DistanceMetric, MatrixExt},
+};
+
 pub type NomResult<I, O> = nom::IResult<I, O, nom::error::VerboseError<I>>;
 
 #[cfg_attr(test, derive(Debug, PartialEq))]
@@ -18,22 +23,36 @@ pub struct Problem {
     pub problem_type: ProblemType,
     pub dimension: usize,
     pub edge_weight_type: EdgeWeightType,
-    pub capacity: usize,
+    pub capacity: f64,
+    /// Empty for EXPLICIT instances, which only have distances.
+    pub coordinates: Vec<(f64, f64)>,
+    /// The z coordinate of each node for EUC_3D instances, empty for every other kind.
+    pub elevations: Vec<f64>,
     pub adjacency_matrix: Matrix,
-    pub demands: Vec<usize>,
+    /// Whether the distance between two nodes is the same in both directions, which 2-opt and
+    /// the pheromone updates assume.
+    pub symmetric: bool,
+    pub demands: Vec<f64>,
+    /// How long a vehicle spends at each node, from the SERVICE_TIME_SECTION, or empty if the
+    /// instance has none.
+    pub service_times: Vec<f64>,
 }
 
 impl Problem {
-    fn parse(i: &str) -> NomResult<&str, Self> {
+    fn parse(i: &str) -> NomResult<&str, ProblemBuilder> {
         // Local use statement so as not to clutter top of file, we need many
         use nom::{
-            error::ParseError,
+            error::{context, ParseError},
             IResult,
-            combinator::{map_res, map_parser},
-            bytes::complete::{tag, take_until1},
-            sequence::{terminated, delimited, preceded, tuple, separated_pair},
-            character::complete::{digit1, space0, space1, line_ending, not_line_ending},
-            multi::count,
+            branch::alt,
+            combinator::{cond, cut, map_opt, map_res, map_parser, opt, recognize, verify},
+            bytes::complete::{tag, take_while_m_n},
+            sequence::{terminated, pair, preceded, tuple, separated_pair},
+            character::complete::{
+                char, digit1, space0, space1, line_ending, multispace0, not_line_ending,
+            },
+            multi::{count, many0, many1},
+            number::complete::double,
         };
 
         /******************************/
@@ -49,8 +68,8 @@ impl Problem {
             terminated(inner, preceded(space0, line_ending))
         }
 
-        /// Parses the key, a colon, the uses the provided parser to parse the value, then parses
-        /// any number of spaces then a line ending.
+        /// Parses the key, a colon with any spaces or tabs around it, then uses the provided
+        /// parser to parse the value, then parses any number of spaces then a line ending.
         fn key_then<'a, F, O, E>(
             key: &'a str,
             value_parser: F,
@@ -60,7 +79,20 @@ impl Problem {
                 E: ParseError<&'a str> + 'a,
                 O: 'a
         {
-            trailing_ws(preceded(tuple((tag(key), tag(" : "))), value_parser))
+            trailing_ws(preceded(tuple((tag(key), space0, char(':'), space0)), value_parser))
+        }
+
+        /// A capacity or demand, which some generators write with thousands separators, e.g.
+        /// `1,000` or `1,000.5`, and otherwise any number `double` accepts.
+        fn quantity(i: &str) -> NomResult<&str, f64> {
+            let group = preceded(char(','), take_while_m_n(3, 3, |c: char| c.is_ascii_digit()));
+            let grouped = recognize(tuple((digit1, many1(group), opt(pair(char('.'), digit1)))));
+            alt((map_res(grouped, |s: &str| s.replace(',', "").parse::<f64>()), double))(i)
+        }
+
+        /// Whether the ids of a section's entries run 1, 2, 3... with no gaps or duplicates.
+        fn sequential_ids<T>(entries: &[(usize, T)]) -> bool {
+            entries.iter().enumerate().all(|(i, (id, _))| *id == i + 1)
         }
 
         /******************************/
@@ -70,10 +102,13 @@ impl Problem {
         // Problem name
         let (i, name) = key_then("NAME", not_line_ending)(i)?;
 
-        // Comment about problem
+        // Comment about problem, in parentheses. Up to the last one on the line, so that the
+        // comment itself may have some
         let (i, comment) = key_then(
             "COMMENT",
-            delimited(tag("("), take_until1(")"), tag(")")),
+            map_opt(not_line_ending, |line: &str| {
+                line.trim_end().strip_prefix('(')?.strip_suffix(')')
+            }),
         )(i)?;
 
         // Type, mapped to ProblemType
@@ -82,10 +117,13 @@ impl Problem {
             ProblemType::parse,
         )(i)?;
 
-        // Dimension, mapped to usize
-        let (i, dimension) = map_res(
-            key_then("DIMENSION", not_line_ending),
-            usize::from_str,
+        // Dimension, mapped to usize. Every instance has at least the depot
+        let (i, dimension) = context(
+            "DIMENSION must be at least 1",
+            verify(
+                map_res(key_then("DIMENSION", not_line_ending), usize::from_str),
+                |&dimension| dimension > 0,
+            ),
         )(i)?;
 
         // Edge weight type, mapped to EdgeWeightType
@@ -94,67 +132,135 @@ impl Problem {
             EdgeWeightType::parse,
         )(i)?;
 
-        // Capacity, mapped to usize
-        let (i, capacity) = map_res(
-            key_then("CAPACITY", not_line_ending),
-            usize::from_str,
-        )(i)?;
-
-        // One coordinate triplet
-        let coordinate = map_res(
-            trailing_ws(
-                preceded(
-                    tuple((space1, digit1, space1)),
-                    separated_pair(digit1, space1, digit1),
-                )
-            ),
-            |(x, y): (&str, &str)| -> Result<_, ParseFloatError> {
-                Ok((x.parse::<f64>()?, y.parse::<f64>()?)) // Discard unneeded values
-            },
+        // EXPLICIT instances say how their distances are laid out. Only the full matrix, as
+        // write_vrp writes it, is supported, so any other layout fails here
+        let explicit = matches!(edge_weight_type, EdgeWeightType::Explicit);
+        let (i, _) = cond(explicit, cut(context(
+            "EXPLICIT instances must have an EDGE_WEIGHT_FORMAT of FULL_MATRIX",
+            key_then("EDGE_WEIGHT_FORMAT", tag("FULL_MATRIX")),
+        )))(i)?;
+
+        // Capacity, which may be fractional. TSP instances have none
+        let (i, capacity) = opt(key_then("CAPACITY", quantity))(i)?;
+
+        // EXPLICIT instances give the distance between every pair of nodes, row by row, in place
+        // of coordinates. A row may be wrapped over several lines
+        let (i, matrix) = cond(explicit, preceded(
+            trailing_ws(tag("EDGE_WEIGHT_SECTION")),
+            trailing_ws(map_res(
+                count(preceded(multispace0, double), dimension * dimension),
+                move |distances: Vec<f64>| Matrix::try_from_rows(
+                    distances.chunks(dimension).map(<[f64]>::to_vec).collect(),
+                ),
+            )),
+        ))(i)?;
+
+        // One coordinate triplet, an id then the coordinates, which may be fractional or negative.
+        // EUC_3D instances have a z coordinate too
+        let three_d = matches!(edge_weight_type, EdgeWeightType::Euc3d);
+        let coordinate = trailing_ws(
+            preceded(
+                space1,
+                separated_pair(
+                    map_res(digit1, usize::from_str),
+                    space1,
+                    tuple((
+                        double,
+                        preceded(space1, double),
+                        cond(three_d, preceded(space1, double)),
+                    )),
+                ),
+            )
         );
 
-        // After the header, get exactly <dimension> tuples of 3 digits separated by spaces,
-        // map them to NodeCoordinates, then Matrix
-        let (i, coordinates) = preceded(
+        // After the header, get exactly <dimension> tuples of 3 numbers separated by spaces,
+        // check their ids are in order, then map them to coordinates
+        let (i, coordinates) = cond(!explicit, preceded(
             trailing_ws(tag("NODE_COORD_SECTION")),
-            count(coordinate, dimension),
-        )(i)?;
-
-        // One demand
-        let demand = map_res(
-            trailing_ws(preceded(tuple((digit1, space1)), digit1)),
-            usize::from_str,
+            context(
+                "NODE_COORD_SECTION ids must run from 1 to DIMENSION",
+                verify(count(coordinate, dimension), sequential_ids),
+            ),
+        ))(i)?;
+        let coordinates = coordinates.unwrap_or_default();
+        let elevations = coordinates.iter().filter_map(|(_, (_, _, z))| *z).collect();
+        let coordinates: Vec<_> = coordinates.into_iter().map(|(_, (x, y, _))| (x, y)).collect();
+
+        // One value per node, an id then a value which may be fractional, as for demands
+        let node_quantity = || trailing_ws(
+            separated_pair(map_res(digit1, usize::from_str), space1, quantity)
         );
 
-        // After the header, get exactly <dimension> pairs of values, 
-        // mapping the second of which to a demand value
-        let (i, demands) = preceded(
+        // After the header, get exactly <dimension> pairs of values, check their ids are in
+        // order, then keep the second of each as a demand value. TSP instances have none
+        let (i, demands) = opt(preceded(
             trailing_ws(tag("DEMAND_SECTION")),
-            count(demand, dimension),
-        )(i)?;
+            context(
+                "DEMAND_SECTION ids must run from 1 to DIMENSION",
+                verify(count(node_quantity(), dimension), sequential_ids),
+            ),
+        ))(i)?;
+        let demands = demands.unwrap_or_default().into_iter().map(|(_, d)| d).collect();
+
+        // Optionally, how long serving each node takes, in the same form as the demands
+        let (i, service_times) = opt(preceded(
+            trailing_ws(tag("SERVICE_TIME_SECTION")),
+            // Committed once the header is seen, so a bad section fails rather than being skipped
+            cut(context(
+                "SERVICE_TIME_SECTION ids must run from 1 to DIMENSION",
+                verify(count(node_quantity(), dimension), sequential_ids),
+            )),
+        ))(i)?;
+        let service_times = service_times.unwrap_or_default().into_iter().map(|(_, t)| t).collect();
+
+        // Optionally, the depot ids terminated by -1. Only node 1 is supported as the depot,
+        // so the ids themselves are ignored
+        let (i, _) = opt(preceded(
+            trailing_ws(tag("DEPOT_SECTION")),
+            terminated(many0(trailing_ws(preceded(space0, digit1))), preceded(space0, tag("-1"))),
+        ))(i)?;
+
+        // Optionally an EOF marker, then whatever whitespace is left
+        let (i, _) = preceded(multispace0, opt(tag("EOF")))(i)?;
+        let (i, _) = multispace0(i)?;
+
+        let builder = ProblemBuilder::new()
+            .name(name)
+            .comment(comment)
+            .problem_type(problem_type)
+            .dimension(dimension)
+            .edge_weight_type(edge_weight_type)
+            .demands(demands)
+            .service_times(service_times);
+        let builder = match matrix {
+            Some(matrix) => builder.matrix(matrix),
+            None => builder.coordinates(coordinates).elevations(elevations),
+        };
 
-        Ok((i, Self {
-            adjacency_matrix: Matrix::adjacency(coordinates),
-            demands,
-            capacity,
-            dimension,
-            problem_type,
-            edge_weight_type,
-            name: name.to_string(),
-            comment: comment.to_string(),
+        Ok((i, match capacity {
+            Some(capacity) => builder.capacity(capacity),
+            None => builder,
         }))
     }
 
-    pub fn try_from_vrp(mut vrp: File) -> Result<Self> {
-        use nom::{Err::{Failure, Error}, Offset};
-        use nom::combinator::complete;
+    pub fn try_from_vrp(vrp: File) -> Result<Self> {
+        Self::from_reader(vrp)
+    }
 
+    /// Reads a whole TSPLIB instance from `reader`, e.g. standard input, then parses it.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
         let mut contents = String::new();
-        vrp.read_to_string(&mut contents)?;
+        reader.read_to_string(&mut contents)?;
+        Self::from_contents(&contents)
+    }
+
+    pub(super) fn from_contents(contents: &str) -> Result<Self> {
+        use nom::{Err::{Failure, Error}, Offset};
+        use nom::combinator::complete;
 
-        let result = match complete(Problem::parse)(&contents) {
-            // Normal parse, return the problem
-            Ok((_, problem)) => Ok(problem),
+        let result = match complete(Problem::parse)(contents) {
+            // Normal parse, check the pieces fit together
+            Ok((_, builder)) => builder.build(),
 
             // Error handling must happen here, since the error type has a string slice
             // into contents, so if we returned that error directly, we would have a slice
@@ -174,41 +280,241 @@ impl Problem {
 
         result
     }
+
+    /// Writes the instance out in the TSPLIB format [`Problem::try_from_vrp`] reads, with the
+    /// depot as node 1. EXPLICIT instances get their distances as a FULL_MATRIX
+    /// EDGE_WEIGHT_SECTION in place of coordinates, and TSP instances leave out the capacity and
+    /// demands.
+    pub fn write_vrp<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let tsp = matches!(self.problem_type, ProblemType::Tsp);
+
+        writeln!(w, "NAME : {}", self.name)?;
+        writeln!(w, "COMMENT : ({})", self.comment)?;
+        writeln!(w, "TYPE : {}", self.problem_type)?;
+        writeln!(w, "DIMENSION : {}", self.dimension)?;
+        writeln!(w, "EDGE_WEIGHT_TYPE : {}", self.edge_weight_type)?;
+        if let EdgeWeightType::Explicit = self.edge_weight_type {
+            writeln!(w, "EDGE_WEIGHT_FORMAT : FULL_MATRIX")?;
+        }
+        if !tsp {
+            writeln!(w, "CAPACITY : {}", self.capacity)?;
+        }
+
+        if let EdgeWeightType::Explicit = self.edge_weight_type {
+            writeln!(w, "EDGE_WEIGHT_SECTION")?;
+            for i in 0..self.dimension {
+                let row: Vec<String> =
+                    self.adjacency_matrix[i].iter().map(f64::to_string).collect();
+                writeln!(w, " {}", row.join(" "))?;
+            }
+        } else {
+            writeln!(w, "NODE_COORD_SECTION")?;
+            for (id, (x, y)) in self.coordinates.iter().enumerate() {
+                write!(w, " {} {} {}", id + 1, x, y)?;
+                if let Some(z) = self.elevations.get(id) {
+                    write!(w, " {}", z)?;
+                }
+                writeln!(w)?;
+            }
+        }
+
+        if !tsp {
+            writeln!(w, "DEMAND_SECTION")?;
+            for (id, demand) in self.demands.iter().enumerate() {
+                writeln!(w, "{} {}", id + 1, demand)?;
+            }
+        }
+
+        if !self.service_times.is_empty() {
+            writeln!(w, "SERVICE_TIME_SECTION")?;
+            for (id, service_time) in self.service_times.iter().enumerate() {
+                writeln!(w, "{} {}", id + 1, service_time)?;
+            }
+        }
+
+        writeln!(w, "DEPOT_SECTION")?;
+        writeln!(w, " 1")?;
+        writeln!(w, " -1")?;
+        writeln!(w, "EOF")
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The number of nodes, the depot included.
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// How much each vehicle can carry, infinite for TSP instances.
+    pub fn capacity(&self) -> f64 {
+        self.capacity
+    }
+
+    /// The demand of each node, the depot's being zero.
+    pub fn demands(&self) -> &[f64] {
+        &self.demands
+    }
+
+    /// How long a vehicle spends at each node, empty if the instance doesn't say.
+    pub fn service_times(&self) -> &[f64] {
+        &self.service_times
+    }
+
+    /// The distances between every pair of nodes, the depot being node 0.
+    pub fn matrix(&self) -> &Matrix {
+        &self.adjacency_matrix
+    }
+
+    /// Takes the instance apart into what a solver needs: the distance matrix, the demand of
+    /// each node and the vehicle capacity.
+    pub fn into_parts(self) -> (Matrix, Vec<f64>, f64) {
+        (self.adjacency_matrix, self.demands, self.capacity)
+    }
+
+    pub fn total_demand(&self) -> f64 {
+        self.demands.iter().sum()
+    }
+
+    /// A lower bound on the vehicles any solution needs, from how many full vehicles it takes to
+    /// carry the total demand.
+    pub fn min_vehicles(&self) -> usize {
+        (self.total_demand() / self.capacity).ceil() as usize
+    }
+
+    /// The number of vehicles the instance's comment says to use, as the CVRPLIB instances
+    /// record, e.g. "No of trucks: 5, Optimal value: 784".
+    pub fn declared_vehicles(&self) -> Option<usize> {
+        use regex::Regex;
+
+        let re = Regex::new(r"No of trucks: (\d+)").unwrap();
+        re.captures(&self.comment)
+            .and_then(|captures| captures[1].parse().ok())
+    }
+
+    /// The Clarke-Wright savings of every pair of nodes, how much shorter driving from `i`
+    /// straight to `j` is than going back to the depot between them, in entry `(i, j)`:
+    /// `d(i, 0) + d(0, j) - d(i, j)`. Negative where the direct edge is the longer way round,
+    /// and zero along the diagonal and for the depot, which save nothing.
+    pub fn savings_matrix(&self) -> Matrix {
+        let matrix = &self.adjacency_matrix;
+        let n = matrix.size();
+        let mut savings = Matrix::new(n);
+        for i in 1..n {
+            for j in 1..n {
+                if i != j {
+                    savings[i][j] = matrix[i][0] + matrix[0][j] - matrix[i][j];
+                }
+            }
+        }
+        savings
+    }
+
+    /// The smallest and largest x and y of any node, as `((min_x, min_y), (max_x, max_y))`, or
+    /// `None` if the instance has no coordinates.
+    pub fn bounding_box(&self) -> Option<((f64, f64), (f64, f64))> {
+        if self.coordinates.is_empty() {
+            return None;
+        }
+
+        Some(self.coordinates.iter().fold(
+            ((f64::INFINITY, f64::INFINITY), (f64::NEG_INFINITY, f64::NEG_INFINITY)),
+            |((min_x, min_y), (max_x, max_y)), &(x, y)| {
+                ((min_x.min(x), min_y.min(y)), (max_x.max(x), max_y.max(y)))
+            },
+        ))
+    }
+
+    /// The optimal (or best known) cost, if the instance's comment records one, as the
+    /// CVRPLIB instances do, e.g. "No of trucks: 5, Optimal value: 784".
+    pub fn optimal_value(&self) -> Option<f64> {
+        use regex::Regex;
+
+        let re = Regex::new(r"(?:Optimal|Best) value: (\d+(?:\.\d+)?)").unwrap();
+        re.captures(&self.comment)
+            .and_then(|captures| captures[1].parse().ok())
+    }
+}
+
+/// A few lines summarizing the instance, to check it parsed as expected.
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.name)?;
+        writeln!(f, "  Type:         {}", self.problem_type)?;
+        writeln!(f, "  Dimension:    {}", self.dimension)?;
+        writeln!(f, "  Capacity:     {}", self.capacity)?;
+        writeln!(f, "  Total demand: {}", self.total_demand())?;
+        writeln!(f, "  Min vehicles: {}", self.min_vehicles())?;
+        if let Some(((min_x, min_y), (max_x, max_y))) = self.bounding_box() {
+            writeln!(f, "  Bounding box: ({}, {}) to ({}, {})", min_x, min_y, max_x, max_y)?;
+        }
+        Ok(())
+    }
 }
 
 #[non_exhaustive]
-#[derive(EnumString)]
+#[derive(EnumString, Display)]
 #[cfg_attr(test, derive(Debug, PartialEq))]
 pub enum ProblemType {
-    #[strum(ascii_case_insensitive)]
+    #[strum(serialize = "CVRP", ascii_case_insensitive)]
     Cvrp,
+    /// A single vehicle with unlimited capacity visiting every node once.
+    #[strum(serialize = "TSP", ascii_case_insensitive)]
+    Tsp,
 }
 
 impl ProblemType {
     pub fn parse(i: &str) -> NomResult<&str, Self> {
         use nom::{
+            branch::alt,
             combinator::map_res,
             bytes::complete::tag,
         };
-        map_res(tag("CVRP"), ProblemType::from_str)(i)
+        map_res(alt((tag("CVRP"), tag("TSP"))), ProblemType::from_str)(i)
     }
 }
 
 #[non_exhaustive]
-#[derive(EnumString)]
+#[derive(EnumString, Display)]
 #[cfg_attr(test, derive(Debug, PartialEq))]
 pub enum EdgeWeightType {
     #[strum(serialize = "EUC_2D")]
     Euc2d,
+    #[strum(serialize = "MAN_2D")]
+    Man2d,
+    #[strum(serialize = "EUC_3D")]
+    Euc3d,
+    #[strum(serialize = "GEO")]
+    Geo,
+    /// Distances given directly rather than measured between coordinates.
+    #[strum(serialize = "EXPLICIT")]
+    Explicit,
 }
 
 impl EdgeWeightType {
     pub fn parse(i: &str) -> NomResult<&str, Self> {
         use nom::{
+            branch::alt,
             combinator::map_res,
             bytes::complete::tag,
         };
-        map_res(tag("EUC_2D"), EdgeWeightType::from_str)(i)
+        map_res(
+            alt((tag("EUC_2D"), tag("MAN_2D"), tag("EUC_3D"), tag("GEO"), tag("EXPLICIT"))),
+            EdgeWeightType::from_str,
+        )(i)
+    }
+
+    /// The metric distances are measured with for this edge weight type, if they are measured
+    /// between 2D coordinates at all. EUC_3D has none, as it needs the elevations too.
+    pub fn metric(&self) -> Option<DistanceMetric> {
+        match self {
+            // Unrounded, which is what the solver has always used
+            EdgeWeightType::Euc2d => Some(DistanceMetric::Euclidean),
+            EdgeWeightType::Man2d => Some(DistanceMetric::Manhattan),
+            EdgeWeightType::Geo => Some(DistanceMetric::Geo),
+            EdgeWeightType::Euc3d | EdgeWeightType::Explicit => None,
+        }
     }
 }
 
@@ -220,6 +526,7 @@ mod tests {
     use anyhow::Result;
 
     use super::*;
+    use crate::aco::utils::tour_cost;
 
     #[test]
     fn test_from_vrp() -> Result<()> {
@@ -230,9 +537,422 @@ mod tests {
         assert_eq!(problem.problem_type, ProblemType::Cvrp);
         assert_eq!(problem.dimension, 32);
         assert_eq!(problem.edge_weight_type, EdgeWeightType::Euc2d);
-        assert_eq!(problem.capacity, 100);
-        assert_eq!(problem.demands[1], 19);
+        assert_eq!(problem.capacity, 100.0);
+        assert_eq!(problem.demands[1], 19.0);
+        assert_eq!(problem.optimal_value(), Some(784.0));
+        assert_eq!(problem.declared_vehicles(), Some(5));
+        assert!(problem.min_vehicles() <= 5);
 
         Ok(())
     }
+
+    const SMALL_VRP: &str = "NAME : small\n\
+        COMMENT : (Hand written)\n\
+        TYPE : CVRP\n\
+        DIMENSION : 3\n\
+        EDGE_WEIGHT_TYPE : EUC_2D\n\
+        CAPACITY : 10\n\
+        NODE_COORD_SECTION\n \
+        1 0 0\n \
+        2 3 4\n \
+        3 6 8\n\
+        DEMAND_SECTION\n\
+        1 0\n\
+        2 5\n\
+        3 5\n";
+
+    #[test]
+    fn test_rejects_out_of_order_ids() {
+        assert!(Problem::parse(SMALL_VRP).is_ok());
+        assert!(Problem::parse(&SMALL_VRP.replace(" 3 6 8", " 2 6 8")).is_err());
+    }
+
+    #[test]
+    fn test_rejects_zero_dimension() {
+        let empty = "NAME : empty\n\
+            COMMENT : (Hand written)\n\
+            TYPE : CVRP\n\
+            DIMENSION : 0\n\
+            EDGE_WEIGHT_TYPE : EUC_2D\n\
+            CAPACITY : 10\n\
+            NODE_COORD_SECTION\n\
+            DEMAND_SECTION\n";
+
+        let err = Problem::from_contents(empty).err().unwrap().to_string();
+        assert!(err.contains("DIMENSION must be at least 1"), "{}", err);
+    }
+
+    #[test]
+    fn test_flexible_separator() -> Result<()> {
+        for separator in [": ", " :", "\t:\t", ":"] {
+            let vrp = SMALL_VRP.replace(" : ", separator);
+            let problem = Problem::from_contents(&vrp)?;
+
+            assert_eq!(problem.name, "small");
+            assert_eq!(problem.capacity, 10.0);
+        }
+
+        assert!(Problem::from_contents(&SMALL_VRP.replace("NAME : ", "NAME ")).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_consumes_depot_section_and_eof() -> Result<()> {
+        let vrp = String::from(SMALL_VRP) + "DEPOT_SECTION\n 1\n -1\nEOF\n\n";
+        let (rest, _) = Problem::parse(&vrp).map_err(|e| anyhow!("{:?}", e))?;
+
+        assert!(rest.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_man_2d_distances() -> Result<()> {
+        let vrp = SMALL_VRP.replace("EUC_2D", "MAN_2D");
+        let problem = Problem::from_contents(&vrp)?;
+
+        assert_eq!(problem.edge_weight_type, EdgeWeightType::Man2d);
+        assert_eq!(problem.adjacency_matrix[0][1], 7.0);
+        assert_eq!(problem.adjacency_matrix[0][2], 14.0);
+        assert_eq!(problem.adjacency_matrix[1][2], 7.0);
+        assert_eq!(problem.adjacency_matrix[2][0], 14.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_euc_3d_distances() -> Result<()> {
+        let vrp = SMALL_VRP
+            .replace("EUC_2D", "EUC_3D")
+            .replace(" 1 0 0\n", " 1 0 0 0\n")
+            .replace(" 2 3 4\n", " 2 1 2 2\n")
+            .replace(" 3 6 8\n", " 3 3 5 8\n");
+        let problem = Problem::from_contents(&vrp)?;
+
+        assert_eq!(problem.edge_weight_type, EdgeWeightType::Euc3d);
+        assert_eq!(problem.elevations, vec![0.0, 2.0, 8.0]);
+        // sqrt(1 + 4 + 4) and sqrt(4 + 9 + 36)
+        assert_eq!(problem.adjacency_matrix[0][1], 3.0);
+        assert_eq!(problem.adjacency_matrix[1][2], 7.0);
+
+        // A missing z is a parse error rather than a silently flat instance
+        assert!(Problem::from_contents(&SMALL_VRP.replace("EUC_2D", "EUC_3D")).is_err());
+
+        let mut written = Vec::new();
+        problem.write_vrp(&mut written)?;
+        let rewritten = Problem::from_contents(std::str::from_utf8(&written)?)?;
+        assert_eq!(rewritten.elevations, problem.elevations);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_geo_distances() -> Result<()> {
+        let problem = Problem::from_contents(BURMA14)?;
+
+        assert_eq!(problem.edge_weight_type, EdgeWeightType::Geo);
+        assert_eq!(problem.adjacency_matrix[0][1], 153.0);
+        // TSPLIB's published optimum for burma14
+        let optimal_tour = [0, 1, 13, 2, 3, 4, 5, 11, 6, 12, 7, 10, 8, 9, 0];
+        assert_eq!(tour_cost(&optimal_tour, &problem.adjacency_matrix), 3323.0);
+
+        Ok(())
+    }
+
+    const BURMA14: &str = "NAME : burma14\n\
+        COMMENT : (14-Staedte in Burma, Zaw Win)\n\
+        TYPE : TSP\n\
+        DIMENSION : 14\n\
+        EDGE_WEIGHT_TYPE : GEO\n\
+        NODE_COORD_SECTION\n \
+        1 16.47 96.10\n \
+        2 16.47 94.44\n \
+        3 20.09 92.54\n \
+        4 22.39 93.37\n \
+        5 25.23 97.24\n \
+        6 22.00 96.05\n \
+        7 20.47 97.02\n \
+        8 17.20 96.29\n \
+        9 16.30 97.38\n \
+        10 14.05 98.12\n \
+        11 16.53 97.38\n \
+        12 21.52 95.59\n \
+        13 19.41 97.13\n \
+        14 20.09 94.55\n\
+        EOF\n";
+
+    const SMALL_TSP: &str = "NAME : small-tsp\n\
+        COMMENT : (Hand written)\n\
+        TYPE : TSP\n\
+        DIMENSION : 5\n\
+        EDGE_WEIGHT_TYPE : EUC_2D\n\
+        NODE_COORD_SECTION\n \
+        1 0 0\n \
+        2 0 10\n \
+        3 10 10\n \
+        4 10 0\n \
+        5 5 12\n\
+        EOF\n";
+
+    const SMALL_EXPLICIT: &str = "NAME : explicit\n\
+        COMMENT : (Hand written)\n\
+        TYPE : CVRP\n\
+        DIMENSION : 3\n\
+        EDGE_WEIGHT_TYPE : EXPLICIT\n\
+        EDGE_WEIGHT_FORMAT : FULL_MATRIX\n\
+        CAPACITY : 10\n\
+        EDGE_WEIGHT_SECTION\n \
+        0 4 7\n \
+        4 0\n \
+        2\n \
+        7 2.5 0\n\
+        DEMAND_SECTION\n\
+        1 0\n\
+        2 5\n\
+        3 5\n\
+        EOF\n";
+
+    #[test]
+    fn test_explicit_full_matrix() -> Result<()> {
+        let problem = Problem::from_contents(SMALL_EXPLICIT)?;
+
+        assert_eq!(problem.edge_weight_type, EdgeWeightType::Explicit);
+        assert!(problem.coordinates.is_empty());
+        assert_eq!(problem.matrix()[0], [0.0, 4.0, 7.0]);
+        assert_eq!(problem.matrix()[1], [4.0, 0.0, 2.0]);
+        assert_eq!(problem.matrix()[2], [7.0, 2.5, 0.0]);
+        assert!(!problem.symmetric);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rejects_other_explicit_formats() {
+        let lower_row = SMALL_EXPLICIT.replace("FULL_MATRIX", "LOWER_ROW");
+        let err = Problem::from_contents(&lower_row).unwrap_err().to_string();
+        assert!(err.contains("EDGE_WEIGHT_FORMAT of FULL_MATRIX"), "{}", err);
+
+        let missing = SMALL_EXPLICIT.replace("EDGE_WEIGHT_FORMAT : FULL_MATRIX\n", "");
+        assert!(Problem::from_contents(&missing).is_err());
+    }
+
+    #[test]
+    fn test_write_vrp_round_trips() -> Result<()> {
+        for vrp in [SMALL_VRP, SMALL_TSP, SMALL_EXPLICIT] {
+            let problem = Problem::from_contents(vrp)?;
+            let mut written = Vec::new();
+            problem.write_vrp(&mut written)?;
+            let reparsed = Problem::from_contents(std::str::from_utf8(&written)?)?;
+            let mut rewritten = Vec::new();
+            reparsed.write_vrp(&mut rewritten)?;
+
+            assert_eq!(reparsed.name, problem.name);
+            assert_eq!(reparsed.problem_type, problem.problem_type);
+            assert_eq!(reparsed.demands, problem.demands);
+            assert_eq!(rewritten, written);
+        }
+
+        Ok(())
+    }
+
+    const DECIMAL_VRP: &str = "NAME : decimal\n\
+        COMMENT : (Hand written)\n\
+        TYPE : CVRP\n\
+        DIMENSION : 3\n\
+        EDGE_WEIGHT_TYPE : EUC_2D\n\
+        CAPACITY : 10\n\
+        NODE_COORD_SECTION\n \
+        1 82.5 91.0\n \
+        2 -3.5 4.25\n \
+        3 1e2 -0.5\n\
+        DEMAND_SECTION\n\
+        1 0\n\
+        2 5\n\
+        3 5\n";
+
+    #[test]
+    fn test_service_times() -> Result<()> {
+        assert!(Problem::from_contents(SMALL_VRP)?.service_times().is_empty());
+
+        let vrp = String::from(SMALL_VRP)
+            + "SERVICE_TIME_SECTION\n1 0\n2 10\n3 2.5\nDEPOT_SECTION\n 1\n -1\n";
+        let problem = Problem::from_contents(&vrp)?;
+        assert_eq!(problem.service_times(), [0.0, 10.0, 2.5]);
+
+        let mut written = Vec::new();
+        problem.write_vrp(&mut written)?;
+        let reparsed = Problem::from_contents(std::str::from_utf8(&written)?)?;
+        assert_eq!(reparsed.service_times, problem.service_times);
+
+        let err = Problem::from_contents(&vrp.replace("3 2.5\n", ""))
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("SERVICE_TIME_SECTION"), "{}", err);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decimal_and_negative_coordinates() -> Result<()> {
+        let problem = Problem::from_contents(DECIMAL_VRP)?;
+
+        assert_eq!(problem.coordinates, vec![(82.5, 91.0), (-3.5, 4.25), (100.0, -0.5)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decimal_and_grouped_quantities() -> Result<()> {
+        let vrp = SMALL_VRP
+            .replace("CAPACITY : 10", "CAPACITY : 1,000.0")
+            .replace("2 5\n", "2 1,000\n")
+            .replace("3 5\n", "3 5.0\n");
+        let problem = Problem::from_contents(&vrp)?;
+
+        assert_eq!(problem.capacity, 1000.0);
+        assert_eq!(problem.demands, vec![0.0, 1000.0, 5.0]);
+
+        // Only whole groups of three are separators
+        assert!(Problem::from_contents(&SMALL_VRP.replace("2 5\n", "2 1,00\n")).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_accessors() -> Result<()> {
+        let problem = Problem::from_contents(SMALL_VRP)?;
+
+        assert_eq!(problem.name(), "small");
+        assert_eq!(problem.dimension(), 3);
+        assert_eq!(problem.capacity(), 10.0);
+        assert_eq!(problem.demands(), [0.0, 5.0, 5.0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_into_parts() -> Result<()> {
+        let problem = Problem::from_contents(SMALL_VRP)?;
+        assert_eq!(problem.matrix()[1][2], 5.0);
+
+        let (matrix, demands, capacity) = problem.into_parts();
+        assert_eq!(matrix.size(), 3);
+        assert_eq!(demands, vec![0.0, 5.0, 5.0]);
+        assert_eq!(capacity, 10.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_savings_matrix() -> Result<()> {
+        let problem = ProblemBuilder::new()
+            .capacity(10.0)
+            .coordinates(vec![(0.0, 0.0), (3.0, 4.0), (6.0, 8.0), (0.0, 5.0)])
+            .demands(vec![0.0, 1.0, 1.0, 1.0])
+            .build()?;
+        let savings = problem.savings_matrix();
+
+        // 5 + 10 - 5, 5 + 5 - sqrt(10) and 10 + 5 - sqrt(45)
+        assert_eq!(savings[1][2], 10.0);
+        assert_eq!(savings[1][3], 10.0 - 10f64.sqrt());
+        assert_eq!(savings[2][3], 15.0 - 45f64.sqrt());
+        assert_eq!(savings[2][1], savings[1][2]);
+        assert_eq!(savings[0][2], 0.0);
+        assert_eq!(savings[3][3], 0.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_min_vehicles() -> Result<()> {
+        let problem = Problem::from_contents(SMALL_VRP)?;
+        assert_eq!(problem.min_vehicles(), 1);
+
+        let problem = Problem::from_contents(&SMALL_VRP.replace("CAPACITY : 10", "CAPACITY : 6"))?;
+        assert_eq!(problem.min_vehicles(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_display() -> Result<()> {
+        let problem = Problem::from_contents(SMALL_VRP)?;
+
+        assert_eq!(
+            problem.to_string(),
+            "small\n  \
+            Type:         CVRP\n  \
+            Dimension:    3\n  \
+            Capacity:     10\n  \
+            Total demand: 10\n  \
+            Min vehicles: 1\n  \
+            Bounding box: (0, 0) to (6, 8)\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tsp_without_capacity_or_demands() -> Result<()> {
+        let problem = Problem::from_contents(SMALL_TSP)?;
+
+        assert_eq!(problem.problem_type, ProblemType::Tsp);
+        assert_eq!(problem.capacity, f64::INFINITY);
+        assert_eq!(problem.demands, vec![0.0; 5]);
+
+        Ok(())
+    }
+
+    mod round_trip {
+        use proptest::{collection::vec, prelude::*};
+
+        use super::*;
+        use crate::aco::{builder::ProblemBuilder, matrix::MatrixExt};
+
+        /// Instances with whole number coordinates, demands and capacity, as TSPLIB writes them.
+        fn problems() -> impl Strategy<Value = Problem> {
+            (2..30usize)
+                .prop_flat_map(|n| {
+                    (
+                        "[A-Za-z0-9 ,:()]{0,20}",
+                        1..1000u32,
+                        vec((0..1000u32, 0..1000u32), n),
+                        vec(0..1000u32, n),
+                    )
+                })
+                .prop_map(|(comment, capacity, coordinates, demands)| {
+                    let coordinates = coordinates.iter().map(|&(x, y)| (x as f64, y as f64));
+                    ProblemBuilder::new()
+                        .name("generated")
+                        .comment(comment)
+                        .capacity(capacity as f64)
+                        .coordinates(coordinates.collect())
+                        .demands(demands.iter().map(|&d| d as f64).collect())
+                        .build()
+                        .unwrap()
+                })
+        }
+
+        proptest! {
+            #[test]
+            fn test_write_then_parse(expected in problems()) {
+                let mut vrp = Vec::new();
+                expected.write_vrp(&mut vrp).unwrap();
+                let parsed = Problem::from_contents(&String::from_utf8(vrp).unwrap()).unwrap();
+
+                prop_assert_eq!(&parsed.comment, &expected.comment);
+                prop_assert_eq!(parsed.dimension, expected.dimension);
+                prop_assert_eq!(parsed.capacity, expected.capacity);
+                prop_assert_eq!(&parsed.demands, &expected.demands);
+                prop_assert_eq!(&parsed.coordinates, &expected.coordinates);
+                prop_assert!(parsed
+                    .adjacency_matrix
+                    .entries()
+                    .zip(expected.adjacency_matrix.entries())
+                    .all(|((_, _, a), (_, _, b))| (a - b).abs() < 1e-9));
+            }
+        }
+    }
 }
@@ -0,0 +1,9 @@
+use std::time::Duration;
+
+/// Total time spent in each phase of a run, summed across all cycles.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Timings {
+    pub ant_construction: Duration,
+    pub local_search: Duration,
+    pub pheromone_update: Duration,
+}
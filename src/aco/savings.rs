@@ -0,0 +1,123 @@
+use std::time::Instant;
+
+#[cfg(feature = "profiling")]
+use super::profiling::Timings;
+use super::{
+    problem::Problem,
+    solution::Solution,
+    utils::{route_costs, tour_cost},
+};
+
+/// Builds a solution with the Clarke-Wright savings heuristic: every customer starts on a route
+/// of its own, then routes are joined end to end in order of how much distance joining them
+/// saves, as long as the joined route still fits in a vehicle.
+pub fn clarke_wright(problem: &Problem) -> Solution {
+    let now = Instant::now();
+    let matrix = &problem.adjacency_matrix;
+    let n = matrix.size();
+    let savings_matrix = problem.savings_matrix();
+
+    let mut savings = Vec::with_capacity(n * n.saturating_sub(1) / 2);
+    for i in 1..n {
+        for j in i + 1..n {
+            let saving = savings_matrix[i][j];
+            if saving > 0.0 {
+                savings.push((saving, i, j));
+            }
+        }
+    }
+    savings.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    // Routes are emptied rather than removed when merged, so route_of stays valid
+    let mut routes: Vec<Vec<usize>> = (1..n).map(|node| vec![node]).collect();
+    let mut loads: Vec<f64> = problem.demands[1..].to_vec();
+    let mut route_of: Vec<usize> = (0..n).map(|node| node.saturating_sub(1)).collect();
+
+    for (_, i, j) in savings {
+        let (a, b) = (route_of[i], route_of[j]);
+        if a == b || loads[a] + loads[b] > problem.capacity {
+            continue;
+        }
+
+        // Both customers have to be next to the depot for the routes to be joined through them
+        let is_end =
+            |route: &[usize], node| route.first() == Some(&node) || route.last() == Some(&node);
+        if !is_end(&routes[a], i) || !is_end(&routes[b], j) {
+            continue;
+        }
+
+        // Orient the routes as ... i -> j ..., reversing them if needed
+        if routes[a].last() != Some(&i) {
+            routes[a].reverse();
+        }
+        if routes[b].first() != Some(&j) {
+            routes[b].reverse();
+        }
+
+        let merged = std::mem::take(&mut routes[b]);
+        for &node in &merged {
+            route_of[node] = a;
+        }
+        routes[a].extend(merged);
+        loads[a] += loads[b];
+        loads[b] = 0.0;
+    }
+
+    let mut tour = vec![0];
+    for route in routes.iter().filter(|route| !route.is_empty()) {
+        tour.extend(route);
+        tour.push(0);
+    }
+
+    Solution {
+        name: problem.name.clone(),
+        cost: tour_cost(&tour, matrix),
+        route_costs: route_costs(&tour, matrix),
+        tour,
+        demands: problem.demands.clone(),
+        capacity: problem.capacity,
+        max_route_distance: None,
+        optimal_value: problem.optimal_value(),
+        seed: None,
+        cycles: 0,
+        elapsed: now.elapsed(),
+        edge_usage: None,
+        #[cfg(feature = "profiling")]
+        timings: Timings::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aco::{builder::ProblemBuilder, utils::validate_tour};
+
+    #[test]
+    fn test_clarke_wright() -> anyhow::Result<()> {
+        // Two pairs of customers on opposite sides of the depot, each pair fitting in one vehicle
+        let problem = ProblemBuilder::new()
+            .capacity(10.0)
+            .coordinates(vec![
+                (0.0, 0.0),
+                (10.0, 0.0),
+                (-10.0, 1.0),
+                (10.0, 1.0),
+                (-10.0, 0.0),
+            ])
+            .demands(vec![0.0, 5.0, 5.0, 5.0, 5.0])
+            .build()?;
+
+        let solution = clarke_wright(&problem);
+
+        validate_tour(&solution.tour, &problem.demands, problem.capacity)?;
+        assert_eq!(solution.route_count(), 2);
+        assert!((solution.cost - 2.0 * (11.0 + 101f64.sqrt())).abs() < 1e-9);
+        assert!(solution.routes().all(|route| {
+            let mut route = route.to_vec();
+            route.sort_unstable();
+            route == [1, 3] || route == [2, 4]
+        }));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use yoos::collections::Matrix;
+
+use super::{matrix::MatrixExt, solution::Solution};
+
+/// When a vehicle reaches one stop of its route.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stop {
+    pub node: usize,
+    /// How far the vehicle has driven since leaving the depot.
+    pub distance: f64,
+    /// How long after leaving the depot the vehicle arrives, counting the service time spent at
+    /// every earlier customer.
+    pub arrival: f64,
+}
+
+/// The stops of one vehicle's route, ending with its return to the depot.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RouteSchedule {
+    pub stops: Vec<Stop>,
+}
+
+impl RouteSchedule {
+    /// When the vehicle is back at the depot.
+    pub fn duration(&self) -> f64 {
+        self.stops.last().map_or(0.0, |stop| stop.arrival)
+    }
+}
+
+/// Estimated arrival times at every stop of `solution`, with vehicles all leaving the depot at
+/// time zero, driving at a constant `speed` and spending `service_time` at each customer.
+pub fn schedule(
+    solution: &Solution,
+    matrix: &Matrix,
+    speed: f64,
+    service_time: f64,
+) -> Vec<RouteSchedule> {
+    solution
+        .routes()
+        .map(|route| {
+            let mut stops = Vec::with_capacity(route.len() + 1);
+            let (mut prev, mut distance, mut arrival) = (0, 0.0, 0.0);
+
+            for &node in route.iter().chain(std::iter::once(&0)) {
+                if prev != 0 {
+                    arrival += service_time;
+                }
+                let leg = matrix.get2(prev, node);
+                distance += leg;
+                arrival += leg / speed;
+                stops.push(Stop {
+                    node,
+                    distance,
+                    arrival,
+                });
+                prev = node;
+            }
+
+            RouteSchedule { stops }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aco::matrix::DistanceMetric;
+
+    #[test]
+    fn test_schedule() {
+        let matrix = Matrix::from_coordinates(
+            &[(0.0, 0.0), (0.0, 10.0), (10.0, 10.0), (20.0, 0.0)],
+            DistanceMetric::Euclidean,
+        );
+        let solution = Solution {
+            demands: vec![0.0; 4],
+            capacity: 1.0,
+            ..Solution::for_test(vec![0, 1, 2, 0, 3, 0], 0.0)
+        };
+
+        let schedules = schedule(&solution, &matrix, 2.0, 1.0);
+
+        assert_eq!(schedules.len(), 2);
+        let arrivals: Vec<f64> = schedules[0].stops.iter().map(|s| s.arrival).collect();
+        assert_eq!(arrivals[..2], [5.0, 11.0]);
+        assert_eq!(schedules[1].stops[0].distance, 20.0);
+        assert_eq!(schedules[1].duration(), 21.0);
+    }
+}
@@ -1,57 +1,272 @@
 use super::{
-    ant::Ant,
-    opt::TwoOptStrategy,
+    ant::{Ant, Exponents},
+    config::{Evaporation, LocalSearchAnts, SimulatorConfig, StagnationPolicy},
+    matrix::{self, MatrixExt},
+    opt::{InterRouteOrOptStrategy, LocalSearch, OptimizationStrategy},
     problem::{Problem},
+    solution::Solution,
+    termination::RunContext,
 };
 
+#[cfg(feature = "profiling")]
+use super::profiling::Timings;
+
+use super::utils::{canonical_routes, load_variance, route_costs, route_count, tour_cost};
+
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use yoos::collections::Matrix;
 
-const MAX_CYCLES: usize = 150;
+/// How many cycles a run gets by default, half of which without improving counts as stagnating.
+pub(crate) const MAX_CYCLES: usize = 150;
 const BEST_TOUR_COST: f64 = f64::MAX;
+// Share of the pheromone kept each cycle at first under entropy-targeted evaporation, and the
+// bounds it is kept within
+const INITIAL_PERSISTENCE: f64 = 0.6;
+const MIN_PERSISTENCE: f64 = 0.1;
+const MAX_PERSISTENCE: f64 = 0.99;
+
+/// `println!`, unless the simulator was configured to be quiet.
+macro_rules! report {
+    ($simulator:expr, $($arg:tt)*) => {
+        if !$simulator.config.quiet {
+            println!($($arg)*);
+        }
+    };
+}
 
 #[derive(Eq, PartialEq)]
 enum Continue {
     Yes,
-    No,
+    Restart,
+}
+
+/// Runs the colony on `problem` and returns the best solution it found.
+pub fn solve(problem: Problem) -> anyhow::Result<Solution> {
+    Simulator::on(problem).run()
+}
+
+/// Everything the colony has learned part way through a run, enough to resume it later with
+/// [`Simulator::restore`], e.g. after writing it out with bincode.
+#[derive(Serialize, Deserialize)]
+pub struct SimulatorState {
+    #[serde(with = "matrix::rows")]
+    pub pheromones: Matrix,
+    pub persistence: f64,
+    pub cur_cycle: usize,
+    pub cycles_since_improvement: usize,
+    pub best_tour_cost: f64,
+    pub best_tour: Vec<usize>,
+    /// Where the ants' random choices had got to, so that the resumed run makes the same ones.
+    pub rng: ChaCha8Rng,
+    /// What the original run was seeded with, which the resumed one reports as its own.
+    pub seed: u64,
+}
+
+/// What happened in one cycle run by [`Simulator::step`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StepResult {
+    /// Whether the cycle found a new best tour.
+    pub improved: bool,
+    pub best_tour_cost: f64,
+    /// Whether the run is over, as decided by the configured termination criterion.
+    pub done: bool,
 }
 
 pub struct Simulator {
+    config: SimulatorConfig,
+
     //  Problem description
+    name: String,
+    optimal_value: Option<f64>,
     adjacency_matrix: Matrix,
-    demands: Vec<usize>,
-    capacity: usize,
+    demands: Vec<f64>,
+    capacity: f64,
 
     // Ant tracking
     ants: Vec<Ant>,
     pheromones: Matrix,
+    // How many ants took each edge on the latest cycle, if counting
+    edge_usage: Option<Matrix>,
+    // Share of the pheromone kept each cycle under entropy-targeted evaporation
+    persistence: f64,
+    // Every random choice the ants make is drawn from here, having been seeded with `seed`
+    rng: ChaCha8Rng,
+    seed: u64,
 
-    // Time out after this hits MAX_CYCLES
+    // When the first cycle started
+    started: Option<Instant>,
     cur_cycle: usize,
     cycles_since_improvement: usize,
 
     // Best path tracking
     best_tour_cost: f64,
     best_tour: Vec<usize>,
+    // Tours as good as the best, driving different routes to it and each other, if collecting
+    ties: Vec<Vec<usize>>,
+
+    // Told about every new best tour, if anyone is listening
+    improvements: Option<Sender<Solution>>,
+
+    #[cfg(feature = "profiling")]
+    timings: Timings,
 }
 
 impl Simulator {
     pub fn on(problem: Problem) -> Self {
+        Self::with_config(problem, SimulatorConfig::default())
+    }
+
+    pub fn with_config(problem: Problem, config: SimulatorConfig) -> Self {
+        if !problem.symmetric {
+            eprintln!(
+                "Warning: {} has an asymmetric distance matrix, but 2-opt assumes routes cost the \
+                 same in either direction",
+                problem.name
+            );
+        }
+
+        if let Some(declared) = problem.declared_vehicles() {
+            if declared < problem.min_vehicles() {
+                eprintln!(
+                    "Warning: {} declares {} vehicles, but its demand needs at least {}",
+                    problem.name,
+                    declared,
+                    problem.min_vehicles()
+                );
+            }
+        }
+
         let num_nodes = problem.adjacency_matrix.size();
+        let seed = config.seed.unwrap_or_else(rand::random);
         Self {
+            config,
+            optimal_value: problem.optimal_value(),
+            name: problem.name,
             adjacency_matrix: problem.adjacency_matrix,
             demands: problem.demands,
             capacity: problem.capacity,
-            ants: Self::init_ants(num_nodes, num_nodes, problem.capacity),
+            ants: Vec::new(),
             pheromones: Self::init_pheromones(num_nodes),
+            edge_usage: None,
+            persistence: INITIAL_PERSISTENCE,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            seed,
+            started: None,
             cur_cycle: 0,
             cycles_since_improvement: 0,
             best_tour_cost: BEST_TOUR_COST,
             best_tour: Vec::new(),
+            ties: Vec::new(),
+            improvements: None,
+            #[cfg(feature = "profiling")]
+            timings: Timings::default(),
+        }
+    }
+
+    /// Like [`Simulator::on`], but starts from a known solution, which becomes the tour to beat
+    /// and has pheromone laid along it as though the star ant had taken it.
+    pub fn warm_start(problem: Problem, initial: Solution) -> Self {
+        Self::warm_start_with_config(problem, SimulatorConfig::default(), initial)
+    }
+
+    /// Like [`Simulator::warm_start`], but with `config`, whose
+    /// [`SimulatorConfig::warm_start_pheromone`] decides whether pheromone is laid along the
+    /// initial tour. None is laid along a tour costing nothing, which would otherwise take all
+    /// of it.
+    pub fn warm_start_with_config(
+        problem: Problem,
+        config: SimulatorConfig,
+        initial: Solution,
+    ) -> Self {
+        let mut simulator = Self::with_config(problem, config);
+
+        if simulator.config.warm_start_pheromone && initial.cost > 0.0 {
+            let pheromone = simulator.config.deposit_q / initial.cost;
+            for (&u, &v) in initial.tour.iter().zip(initial.tour.iter().skip(1)) {
+                simulator.pheromones.update(u, v, |v| v + pheromone);
+            }
+        }
+
+        simulator.best_tour_cost = initial.cost;
+        simulator.best_tour = initial.tour;
+        simulator
+    }
+
+    /// Sends a [`Solution`] to `sender` every time the run finds a new best tour, e.g. to show
+    /// progress on another thread. The run carries on as normal if the receiver is dropped.
+    pub fn send_improvements(&mut self, sender: Sender<Solution>) {
+        self.improvements = Some(sender);
+    }
+
+    /// The pheromone laid on each edge so far, which after a run shows the edges the colony
+    /// converged on.
+    pub fn pheromones(&self) -> &Matrix {
+        &self.pheromones
+    }
+
+    pub fn snapshot(&self) -> SimulatorState {
+        SimulatorState {
+            pheromones: self.pheromones.duplicate(),
+            persistence: self.persistence,
+            cur_cycle: self.cur_cycle,
+            cycles_since_improvement: self.cycles_since_improvement,
+            best_tour_cost: self.best_tour_cost,
+            best_tour: self.best_tour.clone(),
+            rng: self.rng.clone(),
+            seed: self.seed,
         }
     }
 
-    fn init_ants(num_nodes: usize, num_ants: usize, capacity: usize) -> Vec<Ant> {
-        vec![Ant::new(num_nodes, capacity); num_ants]
+    /// Resumes a run on `problem` from a [`SimulatorState`] taken by [`Simulator::snapshot`].
+    /// Given the same config, the resumed run carries on exactly as the original would have.
+    ///
+    /// Fails if the state doesn't fit the problem, having pheromones for a different number of
+    /// nodes or a best tour visiting nodes the problem doesn't have.
+    pub fn restore(problem: Problem, state: SimulatorState) -> anyhow::Result<Self> {
+        Self::restore_with_config(problem, SimulatorConfig::default(), state)
+    }
+
+    /// Like [`Simulator::restore`], with a config other than the default. Its seed is ignored,
+    /// as the state carries on from where the original run's random choices had got to.
+    pub fn restore_with_config(
+        problem: Problem,
+        config: SimulatorConfig,
+        state: SimulatorState,
+    ) -> anyhow::Result<Self> {
+        let num_nodes = problem.adjacency_matrix.size();
+        if state.pheromones.size() != num_nodes {
+            anyhow::bail!(
+                "The saved state has pheromones for {} nodes, but {} has {}",
+                state.pheromones.size(),
+                problem.name,
+                num_nodes
+            );
+        }
+        if let Some(&node) = state.best_tour.iter().find(|&&node| node >= num_nodes) {
+            anyhow::bail!(
+                "The saved state's best tour visits node {}, but {} has only {}",
+                node,
+                problem.name,
+                num_nodes
+            );
+        }
+
+        let mut simulator = Self::with_config(problem, config);
+        simulator.pheromones = state.pheromones;
+        simulator.persistence = state.persistence;
+        simulator.cur_cycle = state.cur_cycle;
+        simulator.cycles_since_improvement = state.cycles_since_improvement;
+        simulator.best_tour_cost = state.best_tour_cost;
+        simulator.best_tour = state.best_tour;
+        simulator.rng = state.rng;
+        simulator.seed = state.seed;
+        Ok(simulator)
     }
 
     fn init_pheromones(n: usize) -> Matrix {
@@ -65,74 +280,371 @@ impl Simulator {
         pheromones
     }
 
-    pub fn run(&mut self) -> anyhow::Result<()> {
-        let now = std::time::Instant::now();
-        while self.should_continue() {
-            self.reset_ants();
-            self.update_ants();
-            if self.try_find_best_tour() == Continue::No {
+    pub fn run(&mut self) -> anyhow::Result<Solution> {
+        let now = Instant::now();
+        self.ensure_depot()?;
+
+        // Without customers the only tour is staying put, and its zero cost would turn the
+        // pheromone updates infinite
+        if self.num_nodes() <= 1 {
+            self.best_tour = vec![0, 0];
+            self.best_tour_cost = 0.0;
+            return Ok(self.solution(now.elapsed()));
+        }
+
+        loop {
+            let step = self.step()?;
+
+            if step.improved {
+                if let Some(improvements) = &self.improvements {
+                    // Nobody listening anymore isn't a reason to stop
+                    let _ = improvements.send(self.solution(now.elapsed()));
+                }
+            }
+
+            if step.done {
+                report!(self, "Stopped on cycle {}", self.cur_cycle);
                 break;
             }
-            self.evaporate();
-            self.update_pheromones()
         }
+
+        if self.config.final_polish {
+            self.polish();
+        }
+
         let time = now.elapsed();
-        println!(
+        report!(
+            self,
             "Best found VRP solutions of cost {} by visiting:",
-            &self.best_tour_cost
+            self.config.cost_format.format(self.best_tour_cost)
         );
-        println!("{}", Self::format_path(&self.best_tour));
-        println!("Took {:?}", time);
+        report!(self, "{}", self.format_path(&self.best_tour));
+        report!(self, "Took {:?}", time);
+
+        // Catches construction or local search bugs that lose or duplicate customers
+        let solution = self.solution(time);
+        solution.validate()?;
+        Ok(solution)
+    }
 
+    /// Fails on a problem without even a depot, which parsing and [`ProblemBuilder`] reject, but
+    /// a problem assembled by hand may not.
+    ///
+    /// [`ProblemBuilder`]: super::ProblemBuilder
+    fn ensure_depot(&self) -> anyhow::Result<()> {
+        if self.num_nodes() == 0 {
+            anyhow::bail!("{} has no nodes, so there is nothing to solve", self.name);
+        }
         Ok(())
     }
 
-    fn update_ants(&mut self) {
-        for ant in &mut self.ants {
-            while !ant.done() {
-                ant.move_to_next(&self.adjacency_matrix, &self.pheromones, &self.demands);
+    /// Like [`Simulator::run`], but with [`SimulatorConfig::collect_ties`] also returns every
+    /// other tour found that is as good as the best but drives different routes. The best comes
+    /// first either way.
+    pub fn run_all(&mut self) -> anyhow::Result<Vec<Solution>> {
+        let best = self.run()?;
+        let mut solutions = Vec::with_capacity(self.ties.len() + 1);
+        for tour in &self.ties {
+            solutions.push(Solution {
+                tour: tour.clone(),
+                cost: tour_cost(tour, &self.adjacency_matrix),
+                route_costs: route_costs(tour, &self.adjacency_matrix),
+                ..self.solution(best.elapsed)
+            });
+        }
+        solutions.insert(0, best);
+        Ok(solutions)
+    }
+
+    /// Runs one cycle: every ant builds a tour, the best tour is updated, then the pheromones are
+    /// evaporated and laid down again, or reset if the search has stagnated.
+    ///
+    /// Fails if a customer's demand is more than a vehicle can carry.
+    pub fn step(&mut self) -> anyhow::Result<StepResult> {
+        self.ensure_depot()?;
+        let started = *self.started.get_or_insert_with(Instant::now);
+        self.cur_cycle += 1;
+        self.reset_ants();
+        self.update_ants()?;
+        if self.config.track_edge_usage {
+            self.count_edge_usage();
+        }
+        let next = self.try_find_best_tour();
+        let improved = self.cycles_since_improvement == 0;
+
+        match next {
+            Continue::Yes => {
+                #[cfg(feature = "profiling")]
+                let now = Instant::now();
+                self.evaporate();
+                self.update_pheromones();
+                #[cfg(feature = "profiling")]
+                {
+                    self.timings.pheromone_update += now.elapsed();
+                }
             }
-            ant.complete(&self.adjacency_matrix);
-            ant.optimize_path(&self.adjacency_matrix, TwoOptStrategy)
+            Continue::Restart => self.restart(),
         }
+
+        let ctx = RunContext {
+            cycle: self.cur_cycle,
+            elapsed: started.elapsed(),
+            best_cost: self.best_tour_cost,
+            cycles_since_improvement: self.cycles_since_improvement,
+            optimal_value: self.optimal_value,
+        };
+        Ok(StepResult {
+            improved,
+            best_tour_cost: self.best_tour_cost,
+            done: self.config.termination.should_stop(&ctx),
+        })
+    }
+
+    /// Runs 2-opt, then Or-opt between routes, then 2-opt again on the best tour, keeping the
+    /// result if its objective is any lower. Or-opt only looks at distance, so a shorter tour may
+    /// still use more vehicles or load them less evenly.
+    fn polish(&mut self) {
+        let between_routes = InterRouteOrOptStrategy {
+            max_route_distance: self.config.max_route_distance,
+            ..InterRouteOrOptStrategy::new(&self.demands, self.capacity)
+        };
+        let (tour, _) = LocalSearch::TwoOpt.optimize(&self.best_tour, &self.adjacency_matrix);
+        let (tour, _) = between_routes.optimize(&tour, &self.adjacency_matrix);
+        let (tour, cost) = LocalSearch::TwoOpt.optimize(&tour, &self.adjacency_matrix);
+
+        let before = self.objective(self.best_tour_cost, &self.best_tour);
+        if before - self.objective(cost, &tour) > self.config.improvement_epsilon {
+            self.ties.clear();
+            let format = &self.config.cost_format;
+            report!(
+                self,
+                "Polishing took the best tour from {} to {}",
+                format.format(self.best_tour_cost),
+                format.format(cost)
+            );
+            self.best_tour = tour;
+            self.best_tour_cost = cost;
+        }
+    }
+
+    /// The best tour so far, as found in `elapsed`.
+    fn solution(&self, elapsed: Duration) -> Solution {
+        Solution {
+            name: self.name.clone(),
+            tour: self.best_tour.clone(),
+            cost: self.best_tour_cost,
+            route_costs: route_costs(&self.best_tour, &self.adjacency_matrix),
+            demands: self.demands.clone(),
+            capacity: self.capacity,
+            max_route_distance: self.config.max_route_distance,
+            optimal_value: self.optimal_value,
+            seed: Some(self.seed),
+            cycles: self.cur_cycle,
+            elapsed,
+            edge_usage: self.edge_usage.as_ref().map(MatrixExt::duplicate),
+            #[cfg(feature = "profiling")]
+            timings: self.timings,
+        }
+    }
+
+    fn update_ants(&mut self) -> anyhow::Result<()> {
+        let attractiveness = Ant::attractiveness(
+            &self.adjacency_matrix,
+            &self.pheromones,
+            self.exponents(),
+            self.config.heuristic.as_ref(),
+        );
+        // Each ant gets its own generator, seeded in turn from the run's, so that the tours built
+        // only depend on the seed and not on how rayon spreads the ants over its threads
+        let seeds: Vec<u64> = (0..self.ants.len()).map(|_| self.rng.gen()).collect();
+        #[cfg(feature = "profiling")]
+        let now = Instant::now();
+        let (adjacency_matrix, demands) = (&self.adjacency_matrix, &self.demands);
+        self.ants
+            .par_iter_mut()
+            .zip(seeds)
+            .try_for_each(|(ant, seed)| {
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                while !ant.done() {
+                    ant.move_to_next(adjacency_matrix, &attractiveness, demands, &mut rng)?;
+                }
+                ant.complete(adjacency_matrix);
+                anyhow::Ok(())
+            })?;
+        #[cfg(feature = "profiling")]
+        {
+            self.timings.ant_construction += now.elapsed();
+        }
+
+        #[cfg(feature = "profiling")]
+        let now = Instant::now();
+        for i in self.ants_to_optimize() {
+            self.ants[i].optimize_path(&self.adjacency_matrix, &self.config.local_search);
+        }
+        #[cfg(feature = "profiling")]
+        {
+            self.timings.local_search += now.elapsed();
+        }
+        Ok(())
+    }
+
+    /// The indices of the ants to run the local search on this cycle, as the config's
+    /// `local_search_ants` and `local_search_every_n_cycles` choose.
+    fn ants_to_optimize(&self) -> Vec<usize> {
+        let every = self.config.local_search_every_n_cycles.max(1);
+        if !self.cur_cycle.saturating_sub(1).is_multiple_of(every) {
+            return Vec::new();
+        }
+
+        let k = match self.config.local_search_ants {
+            LocalSearchAnts::All => return (0..self.ants.len()).collect(),
+            LocalSearchAnts::BestOnly => 1,
+            LocalSearchAnts::TopK(k) => k,
+        };
+        let mut ranked: Vec<usize> = (0..self.ants.len()).collect();
+        ranked.sort_by(|&a, &b| {
+            let key = |i: usize| self.rank_key(&self.ants[i]);
+            key(a).total_cmp(&key(b)).then(a.cmp(&b))
+        });
+        ranked.truncate(k);
+        ranked
+    }
+
+    /// Counts how many of this cycle's ants drove along each edge, in the direction they took
+    /// it. Consecutive depot visits aren't counted as an edge.
+    fn count_edge_usage(&mut self) {
+        let mut usage = Matrix::new(self.num_nodes());
+        for ant in &self.ants {
+            for (&u, &v) in ant.path_taken().iter().zip(ant.path_taken().iter().skip(1)) {
+                if u != v {
+                    usage.update(u, v, |count| count + 1.0);
+                }
+            }
+        }
+        self.edge_usage = Some(usage);
+    }
+
+    /// The number of ants that drove along each edge on the latest cycle, if counting with
+    /// [`SimulatorConfig::track_edge_usage`].
+    pub fn edge_usage(&self) -> Option<&Matrix> {
+        self.edge_usage.as_ref()
     }
 
     fn try_find_best_tour(&mut self) -> Continue {
         let mut found_better = false;
         for ant in self.ants.iter() {
-            let tour_length = ant.path_cost();
-            if self.best_tour_cost > tour_length {
+            let best_objective = self.objective(self.best_tour_cost, &self.best_tour);
+            let objective = self.objective(ant.path_cost(), ant.path_taken());
+            if best_objective - objective > self.config.improvement_epsilon {
                 found_better = true;
-                self.best_tour_cost = tour_length;
+                self.best_tour_cost = ant.path_cost();
                 self.best_tour = ant.path_taken().clone();
+                self.ties.clear();
+            } else if self.config.collect_ties
+                && objective - best_objective <= self.config.improvement_epsilon
+            {
+                let routes = canonical_routes(ant.path_taken());
+                let is_new = |tour: &Vec<usize>| canonical_routes(tour) != routes;
+                if is_new(&self.best_tour) && self.ties.iter().all(is_new) {
+                    self.ties.push(ant.path_taken().clone());
+                }
             }
         }
 
         if found_better {
-            println!(
+            report!(
+                self,
                 "New best found VRP solution of cost {} visiting",
-                self.best_tour_cost
+                self.config.cost_format.format(self.best_tour_cost)
             );
-            println!("Current Paths:");
-            println!("{}", Self::format_path(&self.best_tour));
-            println!("Current cycle: {}", self.cur_cycle);
+            report!(self, "Current Paths:");
+            report!(self, "{}", self.format_path(&self.best_tour));
+            report!(self, "Current cycle: {}", self.cur_cycle);
             self.cycles_since_improvement = 0;
             Continue::Yes
         } else {
-            println!("Could not find route beating {}", self.best_tour_cost);
-            println!("Current cycle: {}", self.cur_cycle);
+            report!(
+                self,
+                "Could not find route beating {}",
+                self.config.cost_format.format(self.best_tour_cost)
+            );
+            report!(self, "Current cycle: {}", self.cur_cycle);
             self.cycles_since_improvement += 1;
-            if self.cycles_since_improvement > MAX_CYCLES / 2 {
-                Continue::No
+            if self.cycles_since_improvement > MAX_CYCLES / 2
+                && self.config.on_stagnation == StagnationPolicy::Restart
+            {
+                Continue::Restart
             } else {
                 Continue::Yes
             }
         }
     }
 
+    /// Forgets everything the colony has learned except the best tour, to escape stagnation.
+    fn restart(&mut self) {
+        report!(self, "Stagnated, restarting pheromones");
+        self.pheromones = Self::init_pheromones(self.num_nodes());
+        self.cycles_since_improvement = 0;
+    }
+
+    /// What tours are compared on: their cost plus any penalties for the vehicles they use and
+    /// for how unevenly those vehicles are loaded.
+    fn objective(&self, cost: f64, tour: &[usize]) -> f64 {
+        let mut objective = cost + self.config.vehicle_penalty * route_count(tour) as f64;
+        if self.config.load_variance_penalty != 0.0 {
+            objective += self.config.load_variance_penalty * load_variance(tour, &self.demands);
+        }
+        objective
+    }
+
+    /// What ants are ranked by, lowest first. `total_cmp` puts a negative NaN before every
+    /// number, so NaN objectives count as infinite to rank them last whatever their sign.
+    fn rank_key(&self, ant: &Ant) -> f64 {
+        let objective = self.objective(ant.path_cost(), ant.path_taken());
+        if objective.is_nan() {
+            f64::INFINITY
+        } else {
+            objective
+        }
+    }
+
+    /// How far the search has drifted towards exploring, from 0.0 right after an improvement to
+    /// 1.0 once it is about to stagnate. Always 0.0 unless the config is adaptive.
+    fn exploration(&self) -> f64 {
+        if !self.config.adaptive {
+            return 0.0;
+        }
+        (self.cycles_since_improvement as f64 / (MAX_CYCLES / 2) as f64).min(1.0)
+    }
+
+    /// The exponents ants weigh edges with this cycle, as scheduled if the config has a schedule,
+    /// with the pheromone's halved at full exploration.
+    fn exponents(&self) -> Exponents {
+        let exponents = match &self.config.exponent_schedule {
+            Some(schedule) => schedule.at(self.cur_cycle.saturating_sub(1)),
+            None => Exponents::default(),
+        };
+        Exponents {
+            pheromone: exponents.pheromone * (1.0 - self.exploration() / 2.0),
+            ..exponents
+        }
+    }
+
     fn evaporate(&mut self) {
-        let avg = self.ants.iter().map(Ant::path_cost).sum::<f64>() / self.ants.len() as f64;
-        let evaporation_factor = 0.5 + 80.0 / avg;
+        let persistence = match self.config.evaporation {
+            Evaporation::Cost => {
+                let avg =
+                    self.ants.iter().map(Ant::path_cost).sum::<f64>() / self.ants.len() as f64;
+                0.5 + 80.0 / avg
+            }
+            Evaporation::Entropy { target, gain } => {
+                self.persistence = (self.persistence + gain * (target - self.pheromone_entropy()))
+                    .clamp(MIN_PERSISTENCE, MAX_PERSISTENCE);
+                self.persistence
+            }
+        };
+        // Keep up to half as much pheromone when exploring
+        let evaporation_factor = persistence * (1.0 - self.exploration() / 2.0);
 
         for i in 0..self.num_nodes() {
             for j in 0..self.num_nodes() {
@@ -140,25 +652,86 @@ impl Simulator {
                 self.pheromones.update(j, i, |v| v * evaporation_factor);
             }
         }
+
+        if let StagnationPolicy::Smooth { after, delta } = self.config.on_stagnation {
+            if self.cycles_since_improvement > after {
+                self.smooth(delta);
+            }
+        }
+    }
+
+    /// Moves the pheromone on every edge between distinct nodes `delta` of the way towards the
+    /// strongest, `tau += delta * (tau_max - tau)`.
+    fn smooth(&mut self, delta: f64) {
+        let tau_max = self
+            .pheromones
+            .entries()
+            .filter(|&(i, j, _)| i != j)
+            .map(|(_, _, v)| v)
+            .fold(0.0, f64::max);
+
+        for i in 0..self.num_nodes() {
+            for j in 0..self.num_nodes() {
+                if i != j {
+                    self.pheromones.update(i, j, |v| v + delta * (tau_max - v));
+                }
+            }
+        }
+    }
+
+    /// The entropy of the pheromone spread over the edges between distinct nodes, divided by its
+    /// maximum so that 1.0 means evenly spread and 0.0 means all on one edge.
+    fn pheromone_entropy(&self) -> f64 {
+        let edges = self.num_nodes() * self.num_nodes().saturating_sub(1);
+        let total: f64 = self
+            .pheromones
+            .entries()
+            .filter(|&(i, j, _)| i != j)
+            .map(|e| e.2)
+            .sum();
+        if edges <= 1 || total <= 0.0 {
+            return 0.0;
+        }
+
+        let entropy: f64 = self
+            .pheromones
+            .entries()
+            .filter(|&(i, j, v)| i != j && v > 0.0)
+            .map(|(_, _, v)| {
+                let p = v / total;
+                -p * p.ln()
+            })
+            .sum();
+        entropy / (edges as f64).ln()
     }
 
     fn update_pheromones(&mut self) {
-        self.ants.sort_unstable_by(|ant1, ant2| {
-            ant1.path_cost().partial_cmp(&ant2.path_cost()).unwrap()
+        // Taken out so the sort can borrow self to compute objectives
+        let mut ants = std::mem::take(&mut self.ants);
+        // A total order, so a NaN cost sorts last instead of panicking, with ties broken on the
+        // path so equally good ants always rank the same way
+        ants.sort_by(|ant1, ant2| {
+            self.rank_key(ant1)
+                .total_cmp(&self.rank_key(ant2))
+                .then_with(|| ant1.path_taken().cmp(ant2.path_taken()))
         });
+        self.ants = ants;
 
         let star_ant = self.ants.first().unwrap();
         let star_path = star_ant.path_taken();
         for i in 0..(star_path.len() - 1) {
-            let pheromone = 3.0 / star_ant.path_cost();
+            let pheromone = self.config.deposit_q / star_ant.path_cost();
             let u = *star_path.get(i).unwrap();
             let v = *star_path.get(i + 1).unwrap();
             self.pheromones.update(u, v, |v| v + pheromone);
         }
 
-        for lambda in 1..3 {
+        // Tiny instances have fewer ants than ranks, and the star ant has laid already
+        let ranked_ants = self.config.ranked_ants.max(1);
+        for lambda in 1..ranked_ants.min(self.ants.len()) {
             let cur_ant = self.ants.get(lambda).unwrap();
-            let pheromone = (3 - lambda) as f64 / cur_ant.path_cost();
+            let rank_weight = (ranked_ants - lambda) as f64 / ranked_ants as f64;
+            let pheromone = self.config.deposit_q * rank_weight / cur_ant.path_cost();
 
             let path_taken = cur_ant.path_taken();
 
@@ -176,27 +749,786 @@ impl Simulator {
     }
 
     fn reset_ants(&mut self) {
-        self.ants = vec![Ant::new(self.num_nodes(), self.capacity); self.num_nodes()];
-    }
+        // The config can't change between cycles, so last cycle's ants only need sending back to
+        // the depot, which keeps their allocations. Built on the first cycle rather than when
+        // the simulator is, as there may not be a depot to start them at.
+        if self.ants.len() == self.num_nodes() {
+            self.ants.iter_mut().for_each(Ant::reset);
+            return;
+        }
 
-    fn should_continue(&mut self) -> bool {
-        self.cur_cycle += 1;
-        self.cur_cycle < MAX_CYCLES
+        let max_route_distance = self.config.max_route_distance;
+        let ant = Ant::new(self.num_nodes(), self.capacity)
+            .with_max_route_distance(max_route_distance)
+            .with_depot_return(self.config.depot_return);
+        self.ants = vec![ant; self.num_nodes()];
+        if self.config.greedy_ant {
+            self.ants[0] = Ant::new_greedy(self.num_nodes(), self.capacity)
+                .with_max_route_distance(max_route_distance);
+        }
     }
 
-    fn format_path(path: &[usize]) -> String {
+    fn format_path(&self, path: &[usize]) -> String {
         let paths = crate::aco::utils::path_to_routes(path);
 
         let mut lines: Vec<String> = Vec::new();
 
         for (i, path) in paths.iter().enumerate() {
-            if path.is_empty() {
-                continue;
+            let line: String = if self.config.depot_bookends {
+                std::iter::once(&0)
+                    .chain(path)
+                    .chain(std::iter::once(&0))
+                    .map(|&p| p.to_string() + " ")
+                    .collect()
+            } else {
+                path.iter().map(|&p| p.to_string() + " ").collect()
             };
-            let line: String = path.iter().map(|&p| p.to_string() + " ").collect();
             lines.push(String::from("Route #") + &(i + 1).to_string() + ": " + &line);
         }
 
         lines.join("\n")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::aco::{
+        builder::ProblemBuilder,
+        config::ExponentSchedule,
+        generate::generate_random,
+        heuristic::{ConstructionHeuristic, DistanceHeuristic, SavingsHeuristic},
+        opt::NoOpStrategy,
+        problem::{EdgeWeightType, ProblemType},
+        savings::clarke_wright,
+        termination::TimeBudget,
+    };
+
+    fn square_problem() -> Problem {
+        ProblemBuilder::new()
+            .name("square")
+            .capacity(10.0)
+            .coordinates(vec![(0.0, 0.0), (0.0, 10.0), (10.0, 10.0), (10.0, 0.0)])
+            .demands(vec![0.0, 5.0, 5.0, 5.0])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_restart_on_stagnation() {
+        let config = SimulatorConfig {
+            on_stagnation: StagnationPolicy::Restart,
+            ..SimulatorConfig::default()
+        };
+        let mut simulator = Simulator::with_config(square_problem(), config);
+
+        // Run a cycle so the pheromones move away from their initial values
+        simulator.reset_ants();
+        simulator.update_ants().unwrap();
+        simulator.try_find_best_tour();
+        simulator.evaporate();
+        simulator.update_pheromones();
+        let best_tour = simulator.best_tour.clone();
+
+        // The same ants can't beat the best tour again, so this counts as stagnating
+        simulator.cycles_since_improvement = MAX_CYCLES / 2;
+        assert!(simulator.try_find_best_tour() == Continue::Restart);
+        simulator.restart();
+
+        let fresh = Simulator::init_pheromones(4);
+        assert!(simulator.pheromones.entries().eq(fresh.entries()));
+        assert_eq!(simulator.cycles_since_improvement, 0);
+        assert_eq!(simulator.best_tour, best_tour);
+    }
+
+    #[test]
+    fn test_smoothing() {
+        // Lay pheromone along some tours so that it isn't all the same
+        let mut simulator = Simulator::on(square_problem());
+        simulator.reset_ants();
+        simulator.update_ants().unwrap();
+        simulator.try_find_best_tour();
+        simulator.update_pheromones();
+
+        let before = simulator.pheromones.duplicate();
+        let tau_max = before.entries().map(|(_, _, v)| v).fold(0.0, f64::max);
+        simulator.smooth(0.5);
+
+        for ((i, j, old), (_, _, new)) in before.entries().zip(simulator.pheromones.entries()) {
+            if i == j {
+                assert_eq!(new, old);
+            } else if old < tau_max {
+                assert!(old < new && new < tau_max);
+                assert!((new - (old + tau_max) / 2.0).abs() < 1e-12);
+            } else {
+                assert_eq!(new, tau_max);
+            }
+        }
+    }
+
+    #[test]
+    fn test_adaptive_exponents() {
+        let config = SimulatorConfig {
+            adaptive: true,
+            ..SimulatorConfig::default()
+        };
+        let mut simulator = Simulator::with_config(square_problem(), config);
+        assert_eq!(simulator.exponents(), Exponents::default());
+
+        simulator.cycles_since_improvement = MAX_CYCLES / 4;
+        let stagnating = simulator.exponents();
+        assert!(stagnating.pheromone < Exponents::default().pheromone);
+
+        simulator.cycles_since_improvement = MAX_CYCLES;
+        assert!(simulator.exponents().pheromone < stagnating.pheromone);
+
+        // Without the option the exponents never change
+        let mut simulator = Simulator::on(square_problem());
+        simulator.cycles_since_improvement = MAX_CYCLES;
+        assert_eq!(simulator.exponents(), Exponents::default());
+    }
+
+    #[test]
+    fn test_vehicle_penalty_changes_the_best_tour() -> anyhow::Result<()> {
+        // Three routes, {1}, {2} and {3, 4}, cost about 61, and the cheapest two, pairing a
+        // far customer with a near one, about 69
+        let problem = || {
+            ProblemBuilder::new()
+                .name("vehicles")
+                .capacity(10.0)
+                .coordinates(vec![
+                    (0.0, 0.0),
+                    (0.0, 10.0),
+                    (0.0, -10.0),
+                    (10.0, 0.0),
+                    (10.0, 1.0),
+                ])
+                .demands(vec![0.0, 6.0, 6.0, 4.0, 4.0])
+                .build()
+        };
+        let config = |vehicle_penalty| SimulatorConfig {
+            vehicle_penalty,
+            heuristic: Arc::new(DistanceHeuristic),
+            quiet: true,
+            seed: Some(1),
+            ..SimulatorConfig::default()
+        };
+
+        let solution = Simulator::with_config(problem()?, config(0.0)).run()?;
+        assert_eq!(solution.route_count(), 3);
+
+        let solution = Simulator::with_config(problem()?, config(10.0)).run()?;
+        assert_eq!(solution.route_count(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_variance_penalty() {
+        let config = SimulatorConfig {
+            load_variance_penalty: 2.0,
+            ..SimulatorConfig::default()
+        };
+        let simulator = Simulator::with_config(square_problem(), config);
+
+        // Loads of 10 and 5, then 5 each
+        assert_eq!(
+            simulator.objective(40.0, &[0, 1, 2, 0, 3, 0]),
+            40.0 + 2.0 * 6.25
+        );
+        assert_eq!(simulator.objective(40.0, &[0, 1, 0, 2, 0, 3, 0]), 40.0);
+
+        let simulator = Simulator::on(square_problem());
+        assert_eq!(simulator.objective(40.0, &[0, 1, 2, 0, 3, 0]), 40.0);
+    }
+
+    #[test]
+    fn test_exponent_schedule() {
+        let schedule = ExponentSchedule {
+            start: Exponents::default(),
+            end: Exponents {
+                savings: 1.0,
+                pheromone: 2.0,
+                distance: 3.0,
+            },
+            cycles: 101,
+        };
+        let config = SimulatorConfig {
+            exponent_schedule: Some(schedule),
+            ..SimulatorConfig::default()
+        };
+        let mut simulator = Simulator::with_config(square_problem(), config);
+
+        // The first cycle
+        simulator.cur_cycle = 1;
+        assert_eq!(simulator.exponents(), schedule.start);
+
+        simulator.cur_cycle = 51;
+        let halfway = simulator.exponents();
+        assert_eq!(halfway.savings, 5.0);
+        assert_eq!(halfway.pheromone, 2.0);
+        assert_eq!(halfway.distance, 4.0);
+
+        // The last cycle, and any after it
+        simulator.cur_cycle = 101;
+        assert_eq!(simulator.exponents(), schedule.end);
+        simulator.cur_cycle = 500;
+        assert_eq!(simulator.exponents(), schedule.end);
+    }
+
+    #[test]
+    fn test_entropy_evaporation() {
+        let config = SimulatorConfig {
+            evaporation: Evaporation::Entropy {
+                target: 0.5,
+                gain: 0.1,
+            },
+            ..SimulatorConfig::default()
+        };
+        let mut simulator = Simulator::with_config(square_problem(), config);
+        assert!((simulator.pheromone_entropy() - 1.0).abs() < 1e-9);
+
+        // Evenly spread pheromone is above the target, so less is kept
+        simulator.reset_ants();
+        simulator.update_ants().unwrap();
+        simulator.evaporate();
+        assert!((simulator.persistence - (INITIAL_PERSISTENCE - 0.05)).abs() < 1e-9);
+
+        // All of it on one edge is below the target, so more is kept
+        simulator.pheromones = Matrix::new(4);
+        simulator.pheromones[0][1] = 1.0;
+        assert_eq!(simulator.pheromone_entropy(), 0.0);
+        simulator.evaporate();
+        assert!((simulator.persistence - INITIAL_PERSISTENCE).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_tsp_visits_every_node_in_one_route() -> anyhow::Result<()> {
+        let problem = ProblemBuilder::new()
+            .problem_type(ProblemType::Tsp)
+            .coordinates(vec![
+                (0.0, 0.0),
+                (0.0, 10.0),
+                (10.0, 10.0),
+                (10.0, 0.0),
+                (5.0, 12.0),
+            ])
+            .build()?;
+        let solution = Simulator::on(problem).run()?;
+
+        assert_eq!(solution.route_count(), 1);
+        assert_eq!(solution.routes().next().unwrap().len(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_restored_run_follows_the_same_trajectory() -> anyhow::Result<()> {
+        let problem = || generate_random(20, 100.0, 3);
+        let config = || SimulatorConfig {
+            heuristic: Arc::new(DistanceHeuristic),
+            quiet: true,
+            seed: Some(11),
+            ..SimulatorConfig::default()
+        };
+        let mut original = Simulator::with_config(problem()?, config());
+        for _ in 0..3 {
+            original.step()?;
+        }
+
+        let saved = bincode::serialize(&original.snapshot())?;
+        let state = bincode::deserialize(&saved)?;
+        let mut restored = Simulator::restore_with_config(problem()?, config(), state)?;
+
+        for _ in 0..5 {
+            assert_eq!(restored.step()?, original.step()?);
+            assert_eq!(restored.best_tour, original.best_tour);
+            assert!(restored.pheromones.entries().eq(original.pheromones.entries()));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_seeded_runs_are_the_same_on_any_number_of_threads() -> anyhow::Result<()> {
+        let run = |threads| -> anyhow::Result<Solution> {
+            let config = SimulatorConfig {
+                heuristic: Arc::new(DistanceHeuristic),
+                quiet: true,
+                seed: Some(5),
+                ..SimulatorConfig::default()
+            };
+            let mut simulator = Simulator::with_config(generate_random(30, 100.0, 2)?, config);
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()?
+                .install(|| simulator.run())
+        };
+
+        let (one, four) = (run(1)?, run(4)?);
+        assert_eq!(one.tour, four.tour);
+        assert_eq!(one.cycles, four.cycles);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_rejects_state_for_another_problem() {
+        let state = Simulator::on(square_problem()).snapshot();
+        let error = Simulator::restore(generate_random(5, 100.0, 1).unwrap(), state)
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("pheromones for 4 nodes"));
+
+        let state = SimulatorState {
+            best_tour: vec![0, 1, 7, 0],
+            ..Simulator::on(square_problem()).snapshot()
+        };
+        assert!(Simulator::restore(square_problem(), state).is_err());
+    }
+
+    #[test]
+    fn test_update_pheromones_ranks_nan_costs_last() {
+        for nan in [f64::NAN, -f64::NAN] {
+            let mut simulator = Simulator::on(square_problem());
+            simulator.reset_ants();
+            simulator.update_ants().unwrap();
+
+            // Recosting a path through a NaN distance makes its cost NaN
+            let nan_path = simulator.ants[0].path_taken().clone();
+            simulator.adjacency_matrix[nan_path[0]][nan_path[1]] = nan;
+            simulator.ants[0].optimize_path(&simulator.adjacency_matrix, &NoOpStrategy);
+            assert!(simulator.ants[0].path_cost().is_nan());
+            assert_eq!(simulator.rank_key(&simulator.ants[0]), f64::INFINITY);
+
+            simulator.update_pheromones();
+
+            let last = simulator.ants.last().unwrap();
+            assert!(last.path_cost().is_nan());
+            let ranked = &simulator.ants[..simulator.ants.len() - 1];
+            assert!(ranked.windows(2).all(|w| {
+                (w[0].path_cost(), w[0].path_taken()) <= (w[1].path_cost(), w[1].path_taken())
+            }));
+        }
+    }
+
+    #[test]
+    fn test_send_improvements() -> anyhow::Result<()> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut simulator = Simulator::on(square_problem());
+        simulator.send_improvements(sender);
+        let solution = simulator.run()?;
+
+        let improvements: Vec<Solution> = receiver.try_iter().collect();
+        assert!(!improvements.is_empty());
+        assert!(improvements.windows(2).all(|w| w[1].cost < w[0].cost));
+        assert_eq!(improvements.last().unwrap().cost, solution.cost);
+
+        // A dropped receiver doesn't stop the run
+        let (sender, receiver) = std::sync::mpsc::channel();
+        drop(receiver);
+        let mut simulator = Simulator::on(square_problem());
+        simulator.send_improvements(sender);
+        simulator.run()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_step() -> anyhow::Result<()> {
+        let mut simulator = Simulator::on(square_problem());
+
+        let first = simulator.step()?;
+        assert!(first.improved);
+        assert!(first.best_tour_cost.is_finite());
+        assert_eq!(simulator.cur_cycle, 1);
+
+        let mut last = first;
+        while !last.done {
+            let step = simulator.step()?;
+            assert!(step.best_tour_cost <= last.best_tour_cost);
+            assert_eq!(step.improved, step.best_tour_cost < last.best_tour_cost);
+            last = step;
+        }
+        assert!(simulator.cur_cycle < MAX_CYCLES);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_time_limit() -> anyhow::Result<()> {
+        let config = SimulatorConfig {
+            termination: Arc::new(TimeBudget(Duration::ZERO)),
+            ..SimulatorConfig::default()
+        };
+        let solution = Simulator::with_config(square_problem(), config).run()?;
+
+        assert_eq!(solution.cycles, 1);
+        assert!(solution.cost.is_finite());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_customer_over_capacity_is_an_error() -> anyhow::Result<()> {
+        let problem = ProblemBuilder::new()
+            .capacity(10.0)
+            .coordinates(vec![(0.0, 0.0), (0.0, 10.0), (10.0, 10.0)])
+            .demands(vec![0.0, 5.0, 15.0])
+            .build()?;
+
+        match Simulator::on(problem).run() {
+            Err(err) => assert!(err.to_string().contains("node 3")),
+            Ok(_) => panic!("Solved an instance no vehicle can serve"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_collect_ties() -> anyhow::Result<()> {
+        // Serving 1 and 2 together or 2 and 3 together costs the same, and nothing else does.
+        // Leaving the depot has no savings, so under the default heuristic every ant would head
+        // for 1 first. Seeded, so that the ants are sure to find both
+        let config = SimulatorConfig {
+            collect_ties: true,
+            heuristic: Arc::new(DistanceHeuristic),
+            seed: Some(1),
+            ..SimulatorConfig::default()
+        };
+        let solutions = Simulator::with_config(square_problem(), config).run_all()?;
+
+        assert_eq!(solutions.len(), 2);
+        let best = &solutions[0];
+        for (i, solution) in solutions.iter().enumerate() {
+            assert!((solution.cost - best.cost).abs() < 1e-9);
+            solution.validate()?;
+            for other in &solutions[..i] {
+                assert!(!solution.diff(other).same_routes());
+            }
+        }
+
+        assert_eq!(Simulator::on(square_problem()).run_all()?.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ties_have_their_own_route_costs() -> anyhow::Result<()> {
+        // Serving 1 and 2 together or 1 and 3 together both cost 20, in routes of 6 and 14 or
+        // of 8 and 12
+        let problem = ProblemBuilder::new()
+            .name("ties")
+            .edge_weight_type(EdgeWeightType::Man2d)
+            .capacity(10.0)
+            .coordinates(vec![(0.0, 0.0), (-3.0, -3.0), (-3.0, 0.0), (1.0, -3.0)])
+            .demands(vec![0.0, 5.0, 5.0, 5.0])
+            .build()?;
+        let matrix = problem.adjacency_matrix.duplicate();
+        let config = SimulatorConfig {
+            collect_ties: true,
+            heuristic: Arc::new(DistanceHeuristic),
+            quiet: true,
+            seed: Some(1),
+            ..SimulatorConfig::default()
+        };
+        let solutions = Simulator::with_config(problem, config).run_all()?;
+
+        assert_eq!(solutions.len(), 2);
+        for solution in &solutions {
+            assert_eq!(solution.cost, 20.0);
+            assert_eq!(solution.route_costs, route_costs(&solution.tour, &matrix));
+        }
+        let makespans: Vec<f64> = solutions.iter().map(|s| s.objectives().makespan).collect();
+        assert!(makespans.contains(&14.0) && makespans.contains(&12.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_local_search_frequency() {
+        let config = SimulatorConfig {
+            local_search: LocalSearch::None,
+            local_search_ants: LocalSearchAnts::TopK(2),
+            local_search_every_n_cycles: 2,
+            ..SimulatorConfig::default()
+        };
+        let mut simulator = Simulator::with_config(square_problem(), config);
+        simulator.cur_cycle = 1;
+        simulator.reset_ants();
+        simulator.update_ants().unwrap();
+
+        let chosen = simulator.ants_to_optimize();
+        assert_eq!(chosen.len(), 2);
+        let worst_chosen = chosen
+            .iter()
+            .map(|&i| simulator.ants[i].path_cost())
+            .fold(0.0, f64::max);
+        assert!(simulator
+            .ants
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !chosen.contains(i))
+            .all(|(_, ant)| ant.path_cost() >= worst_chosen));
+
+        // Every other cycle, starting with the first
+        simulator.cur_cycle = 2;
+        assert!(simulator.ants_to_optimize().is_empty());
+        simulator.cur_cycle = 3;
+        assert_eq!(simulator.ants_to_optimize(), chosen);
+
+        simulator.config.local_search_ants = LocalSearchAnts::All;
+        assert_eq!(simulator.ants_to_optimize(), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_edge_usage() -> anyhow::Result<()> {
+        let config = SimulatorConfig {
+            track_edge_usage: true,
+            ..SimulatorConfig::default()
+        };
+        let mut simulator = Simulator::with_config(square_problem(), config);
+        assert!(simulator.edge_usage().is_none());
+        simulator.step()?;
+
+        // Every ant leaves the depot once per route
+        let usage = simulator.edge_usage().unwrap();
+        let departures: f64 = usage[0].iter().sum();
+        let routes: usize = simulator
+            .ants
+            .iter()
+            .map(|ant| route_count(ant.path_taken()))
+            .sum();
+        assert_eq!(departures, routes as f64);
+        assert!(usage.entries().all(|(i, j, count)| i != j || count == 0.0));
+
+        let solution = simulator.run()?;
+        assert!(solution.edge_usage.is_some());
+        assert!(Simulator::on(square_problem()).run()?.edge_usage.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_route_distance() -> anyhow::Result<()> {
+        // Any one vehicle could carry everything, but can't drive around the whole square, which
+        // polishing mustn't undo by merging routes
+        let problem = ProblemBuilder::new()
+            .capacity(100.0)
+            .coordinates(vec![(0.0, 0.0), (0.0, 10.0), (10.0, 10.0), (10.0, 0.0)])
+            .demands(vec![0.0, 5.0, 5.0, 5.0])
+            .build()?;
+        let config = SimulatorConfig {
+            max_route_distance: Some(35.0),
+            final_polish: true,
+            ..SimulatorConfig::default()
+        };
+        // Running validates every route against the maximum
+        let solution = Simulator::with_config(problem, config).run()?;
+
+        assert!(solution.route_count() > 1);
+        assert!(solution.route_costs.iter().all(|&distance| distance <= 35.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_polish_keeps_the_better_objective() -> anyhow::Result<()> {
+        // Moving customer 1 onto the route of 2 saves 20 but leaves the loads at 10 and 5
+        let problem = || {
+            ProblemBuilder::new()
+                .capacity(10.0)
+                .coordinates(vec![(0.0, 0.0), (10.0, 0.0), (11.0, 0.0), (0.0, 10.0)])
+                .demands(vec![0.0, 5.0, 5.0, 5.0])
+                .build()
+        };
+        let even = vec![0, 1, 0, 2, 0, 3, 0];
+
+        for (load_variance_penalty, polished) in [(0.0, true), (1000.0, false)] {
+            let config = SimulatorConfig {
+                load_variance_penalty,
+                ..SimulatorConfig::default()
+            };
+            let mut simulator = Simulator::with_config(problem()?, config);
+            simulator.best_tour = even.clone();
+            simulator.best_tour_cost = tour_cost(&even, &simulator.adjacency_matrix);
+            simulator.polish();
+
+            assert_eq!(simulator.best_tour != even, polished);
+            assert_eq!(simulator.best_tour_cost < 62.0, polished);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_warm_start_never_ends_worse_than_it_began() -> anyhow::Result<()> {
+        for warm_start_pheromone in [true, false] {
+            let problem = generate_random(20, 100.0, 4)?;
+            let initial = clarke_wright(&problem);
+            let initial_cost = initial.cost;
+            let config = SimulatorConfig {
+                heuristic: Arc::new(DistanceHeuristic),
+                warm_start_pheromone,
+                quiet: true,
+                seed: Some(4),
+                ..SimulatorConfig::default()
+            };
+            let n = problem.dimension;
+            let mut simulator = Simulator::warm_start_with_config(problem, config, initial);
+            let (pheromones, fresh) = (simulator.pheromones(), Simulator::init_pheromones(n));
+            let laid = (0..n).any(|i| pheromones[i] != fresh[i]);
+            assert_eq!(laid, warm_start_pheromone);
+
+            let solution = simulator.run()?;
+            assert!(solution.cost <= initial_cost, "{} > {}", solution.cost, initial_cost);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_warm_start_from_a_free_tour() -> anyhow::Result<()> {
+        // Every customer is at the depot, so the tour costs nothing
+        let problem = ProblemBuilder::new()
+            .capacity(10.0)
+            .coordinates(vec![(0.0, 0.0); 3])
+            .demands(vec![0.0, 5.0, 5.0])
+            .build()?;
+        let initial = Solution::from_routes(
+            vec![vec![1, 2]],
+            problem.matrix(),
+            problem.demands.clone(),
+            problem.capacity,
+        )?;
+        assert_eq!(initial.cost, 0.0);
+
+        let simulator = Simulator::warm_start(problem, initial);
+        let pheromones = simulator.pheromones();
+        assert!((0..3).all(|i| pheromones[i].iter().all(|p| p.is_finite())));
+        assert_eq!(simulator.best_tour_cost, 0.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_problem_is_an_error() {
+        let mut problem = square_problem();
+        problem.dimension = 0;
+        problem.adjacency_matrix = Matrix::new(0);
+        problem.demands.clear();
+        problem.coordinates.clear();
+
+        let mut simulator = Simulator::on(problem);
+        assert!(simulator.step().is_err());
+        assert!(simulator.run().is_err());
+    }
+
+    #[test]
+    fn test_tiny_instances() -> anyhow::Result<()> {
+        let problem = |coordinates: Vec<(f64, f64)>| {
+            let demands = vec![1.0; coordinates.len()];
+            ProblemBuilder::new()
+                .capacity(10.0)
+                .coordinates(coordinates)
+                .demands(demands)
+                .build()
+        };
+
+        let depot_only = Simulator::on(problem(vec![(0.0, 0.0)])?).run()?;
+        assert_eq!(depot_only.cost, 0.0);
+        assert_eq!(depot_only.route_count(), 0);
+
+        let one_customer = Simulator::on(problem(vec![(0.0, 0.0), (3.0, 4.0)])?).run()?;
+        assert_eq!(one_customer.cost, 10.0);
+        assert_eq!(one_customer.route_count(), 1);
+
+        let two_customers =
+            Simulator::on(problem(vec![(0.0, 0.0), (3.0, 4.0), (3.0, 0.0)])?).run()?;
+        assert_eq!(two_customers.cost, 12.0);
+        assert_eq!(two_customers.route_count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_colocated_nodes() -> anyhow::Result<()> {
+        // A customer at the depot, and two sharing a spot
+        let coordinates = vec![(0.0, 0.0), (0.0, 0.0), (3.0, 4.0), (3.0, 4.0), (6.0, 0.0)];
+        for heuristic in [
+            Arc::new(SavingsHeuristic) as Arc<dyn ConstructionHeuristic>,
+            Arc::new(DistanceHeuristic),
+        ] {
+            let problem = ProblemBuilder::new()
+                .capacity(10.0)
+                .coordinates(coordinates.clone())
+                .demands(vec![0.0, 2.0, 2.0, 2.0, 2.0])
+                .build()?;
+            let config = SimulatorConfig {
+                heuristic: heuristic.clone(),
+                ..SimulatorConfig::default()
+            };
+            let mut simulator = Simulator::with_config(problem, config);
+
+            let attractiveness = Ant::attractiveness(
+                &simulator.adjacency_matrix,
+                &simulator.pheromones,
+                simulator.exponents(),
+                heuristic.as_ref(),
+            );
+            // Edges out of the depot save nothing, so their weight of -inf is expected
+            assert!(attractiveness
+                .entries()
+                .all(|(_, _, weight)| !weight.is_nan() && weight < f64::INFINITY));
+
+            let solution = simulator.run()?;
+            solution.validate()?;
+            assert!(solution.cost.is_finite());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_only_ranked_ants_deposit() {
+        // No ranked ants still lets the star ant lay, the same as one
+        for ranked_ants in [0, 1] {
+            let config = SimulatorConfig {
+                ranked_ants,
+                ..SimulatorConfig::default()
+            };
+            let mut simulator = Simulator::with_config(square_problem(), config);
+            simulator.reset_ants();
+            simulator.update_ants().unwrap();
+            simulator.update_pheromones();
+
+            let star_path = simulator.ants[0].path_taken();
+            let fresh = Simulator::init_pheromones(4);
+            for (u, v, pheromone) in simulator.pheromones.entries() {
+                let on_star_path = star_path.windows(2).any(|w| w == [u, v]);
+                assert_eq!(pheromone != fresh.get2(u, v), on_star_path);
+            }
+        }
+    }
+
+    #[test]
+    fn test_negligible_improvements_count_as_stagnation() {
+        let mut simulator = Simulator::on(square_problem());
+        simulator.reset_ants();
+        simulator.update_ants().unwrap();
+
+        let cheapest = simulator
+            .ants
+            .iter()
+            .map(Ant::path_cost)
+            .fold(f64::INFINITY, f64::min);
+        simulator.best_tour_cost = cheapest + 1e-12;
+        simulator.best_tour = vec![0, 1, 2, 3, 0];
+
+        simulator.try_find_best_tour();
+        assert_eq!(simulator.cycles_since_improvement, 1);
+        assert_eq!(simulator.best_tour_cost, cheapest + 1e-12);
+    }
+}
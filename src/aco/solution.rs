@@ -0,0 +1,761 @@
+use std::fmt::{self, Write};
+use std::io::{self, Read};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use yoos::collections::Matrix;
+
+#[cfg(feature = "profiling")]
+use super::profiling::Timings;
+use super::{
+    problem::Problem,
+    utils::{
+        format_cost, load_variance, path_to_routes, route_costs, route_load, tour_cost,
+        validate_route_distances, validate_tour,
+    },
+};
+
+/// The best tour found for a problem, along with some statistics about the run that found it.
+pub struct Solution {
+    pub name: String,
+    pub tour: Vec<usize>,
+    pub cost: f64,
+    /// The distance driven on each route, in the order of [`Solution::routes`].
+    pub route_costs: Vec<f64>,
+    pub demands: Vec<f64>,
+    pub capacity: f64,
+    /// The furthest any one route may drive, if the run was limited.
+    pub max_route_distance: Option<f64>,
+    pub optimal_value: Option<f64>,
+    /// What the run that found the tour seeded its random choices with, which repeats it given
+    /// the same instance and config. `None` for tours found some other way, e.g. read from a
+    /// file.
+    pub seed: Option<u64>,
+    pub cycles: usize,
+    pub elapsed: Duration,
+    /// How many ants drove from `i` to `j` on the last cycle, in entry `(i, j)`, if the run was
+    /// configured to count.
+    pub edge_usage: Option<Matrix>,
+    #[cfg(feature = "profiling")]
+    pub timings: Timings,
+}
+
+/// How one solution differs from another, from [`Solution::diff`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolutionDiff {
+    /// How much more the other solution costs, negative if it is cheaper.
+    pub cost_delta: f64,
+    /// The indices of this solution's routes that the other doesn't have, in either direction.
+    pub changed_routes: Vec<usize>,
+    /// Customers that share a route with different customers in the other solution, in order.
+    pub moved_customers: Vec<usize>,
+}
+
+impl SolutionDiff {
+    /// Whether both solutions drive the same routes, perhaps in a different order or direction.
+    pub fn same_routes(&self) -> bool {
+        self.changed_routes.is_empty() && self.moved_customers.is_empty()
+    }
+}
+
+/// The measures a solution can be judged by besides its distance, from
+/// [`Solution::objectives`]. The search only ever minimizes distance, but the others help choose
+/// between solutions it found, e.g. trading a longer total distance for fewer vehicles.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Objectives {
+    /// The total distance driven, the solution's cost.
+    pub distance: f64,
+    /// The number of vehicles, one per route.
+    pub vehicles: usize,
+    /// The distance driven on the longest route, which is how long the whole solution takes to
+    /// drive when the vehicles set out together.
+    pub makespan: f64,
+    /// The variance of the routes' loads, 0 when every vehicle carries the same, as
+    /// [`SimulatorConfig::load_variance_penalty`] weighs it.
+    ///
+    /// [`SimulatorConfig::load_variance_penalty`]: super::SimulatorConfig::load_variance_penalty
+    pub load_variance: f64,
+}
+
+/// What each of the [`Objectives`] is worth, to combine them into a single score.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ObjectiveWeights {
+    pub distance: f64,
+    /// Per vehicle, e.g. the fixed cost of sending one out.
+    pub vehicles: f64,
+    pub makespan: f64,
+    pub load_variance: f64,
+}
+
+impl Default for ObjectiveWeights {
+    /// Only the distance counts, as in the search.
+    fn default() -> Self {
+        Self {
+            distance: 1.0,
+            vehicles: 0.0,
+            makespan: 0.0,
+            load_variance: 0.0,
+        }
+    }
+}
+
+impl Objectives {
+    /// The objectives weighed and summed, lower being better.
+    pub fn weighted(&self, weights: &ObjectiveWeights) -> f64 {
+        weights.distance * self.distance
+            + weights.vehicles * self.vehicles as f64
+            + weights.makespan * self.makespan
+            + weights.load_variance * self.load_variance
+    }
+}
+
+impl fmt::Display for Objectives {
+    /// As in `distance 784, 5 vehicles, makespan 212.50, load variance 36`, the numbers
+    /// formatted as costs are for [`Solution`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precision = f.precision().unwrap_or(2);
+        write!(
+            f,
+            "distance {}, {} vehicles, makespan {}, load variance {}",
+            format_cost(self.distance, precision),
+            self.vehicles,
+            format_cost(self.makespan, precision),
+            format_cost(self.load_variance, precision)
+        )
+    }
+}
+
+impl Solution {
+    /// A solution driving `routes`, each a list of customers without the depot, e.g. one read
+    /// from a `.sol` file. Fails if the routes don't pass [`Solution::validate`].
+    pub fn from_routes(
+        routes: Vec<Vec<usize>>,
+        matrix: &Matrix,
+        demands: Vec<f64>,
+        capacity: f64,
+    ) -> Result<Solution> {
+        let solution = Solution::partial_from_routes(routes, matrix, demands, capacity)?;
+        solution.validate()?;
+        Ok(solution)
+    }
+
+    /// Like [`Solution::from_routes`], but the routes needn't visit every customer, e.g. to
+    /// build the pieces for [`Solution::merge`]. Fails if the routes don't pass
+    /// [`validate_tour`].
+    pub fn partial_from_routes(
+        routes: Vec<Vec<usize>>,
+        matrix: &Matrix,
+        demands: Vec<f64>,
+        capacity: f64,
+    ) -> Result<Solution> {
+        let mut tour = vec![0];
+        for route in routes.iter().filter(|route| !route.is_empty()) {
+            tour.extend(route);
+            tour.push(0);
+        }
+        // Only once validated, as a node that doesn't exist isn't in the matrix either
+        validate_tour(&tour, &demands, capacity)?;
+        Ok(Solution {
+            name: String::new(),
+            cost: tour_cost(&tour, matrix),
+            route_costs: route_costs(&tour, matrix),
+            tour,
+            demands,
+            capacity,
+            max_route_distance: None,
+            optimal_value: None,
+            seed: None,
+            cycles: 0,
+            elapsed: Duration::ZERO,
+            edge_usage: None,
+            #[cfg(feature = "profiling")]
+            timings: Timings::default(),
+        })
+    }
+
+    /// Reads a solution to `problem` in the CVRPLIB `.sol` format, one `Route #1: 3 1 2` line per
+    /// route followed by an optional `Cost 123` line. The file numbers customers from 1 without
+    /// the depot, which lines up with the depot being node 0 here. The cost is recomputed rather
+    /// than trusted, as the file's may be rounded.
+    pub fn from_sol<R: Read>(mut reader: R, problem: &Problem) -> Result<Solution> {
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+
+        let mut routes = Vec::new();
+        for line in contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+        {
+            if let Some(route) = line.strip_prefix("Route #") {
+                let (_, customers) = route
+                    .split_once(':')
+                    .ok_or_else(|| anyhow!("Missing ':' in {:?}", line))?;
+                let route = customers
+                    .split_whitespace()
+                    .map(|id| match id.parse() {
+                        Ok(0) => bail!("Routes can't visit the depot, in {:?}", line),
+                        Ok(node) => Ok(node),
+                        Err(_) => bail!("Invalid customer {:?} in {:?}", id, line),
+                    })
+                    .collect::<Result<_>>()?;
+                routes.push(route);
+            } else if let Some(cost) = line.strip_prefix("Cost") {
+                cost.trim()
+                    .parse::<f64>()
+                    .map_err(|_| anyhow!("Invalid cost in {:?}", line))?;
+            } else {
+                bail!("Unexpected line {:?}", line);
+            }
+        }
+
+        let mut solution = Solution::from_routes(
+            routes,
+            problem.matrix(),
+            problem.demands.clone(),
+            problem.capacity,
+        )?;
+        solution.name = problem.name.clone();
+        solution.optimal_value = problem.optimal_value();
+        Ok(solution)
+    }
+
+    /// Reads a tour of `problem` in the TSPLIB `.tour` format, as benchmark suites ship their
+    /// optimal tours, e.g. `instance.opt.tour`. The `TOUR_SECTION` lists node ids from 1, the
+    /// depot being 1, until a `-1`. Ids past the dimension stand for extra visits to the depot,
+    /// as TSPLIB writes tours with several vehicles. The tour needn't start at the depot.
+    pub fn from_tour<R: Read>(mut reader: R, problem: &Problem) -> Result<Solution> {
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+
+        let (_, section) = contents
+            .split_once("TOUR_SECTION")
+            .ok_or_else(|| anyhow!("Missing TOUR_SECTION"))?;
+        let mut tour = Vec::new();
+        for id in section.split_whitespace() {
+            match id.parse::<i64>() {
+                Ok(-1) => break,
+                Ok(id) if id >= 1 => {
+                    let node = id as usize - 1;
+                    tour.push(if node < problem.dimension { node } else { 0 });
+                }
+                _ => bail!("Invalid node {:?} in TOUR_SECTION", id),
+            }
+        }
+
+        let start = tour
+            .iter()
+            .position(|&node| node == 0)
+            .ok_or_else(|| anyhow!("The tour never visits the depot"))?;
+        tour.rotate_left(start);
+
+        let mut solution = Solution::from_routes(
+            path_to_routes(&tour),
+            problem.matrix(),
+            problem.demands.clone(),
+            problem.capacity,
+        )?;
+        solution.name = problem.name.clone();
+        solution.optimal_value = problem.optimal_value();
+        Ok(solution)
+    }
+
+    /// Checks that the tour is feasible, as [`validate_tour`] does, that it visits every
+    /// customer exactly once, and that no route drives further than
+    /// [`Solution::max_route_distance`].
+    pub fn validate(&self) -> Result<()> {
+        validate_tour(&self.tour, &self.demands, self.capacity)?;
+        if let Some(max_route_distance) = self.max_route_distance {
+            validate_route_distances(&self.route_costs, max_route_distance)?;
+        }
+
+        let mut visits = vec![0; self.demands.len()];
+        for &node in self.tour.iter().filter(|&&node| node != 0) {
+            visits[node] += 1;
+        }
+        // Numbered as the routes print them
+        let customers = |count: fn(usize) -> bool| -> Vec<usize> {
+            (1..visits.len())
+                .filter(|&node| count(visits[node]))
+                .collect()
+        };
+        let (missing, repeated) = (customers(|n| n == 0), customers(|n| n > 1));
+
+        if !missing.is_empty() || !repeated.is_empty() {
+            bail!(
+                "Every customer must be visited exactly once, but {:?} never are and {:?} are \
+                 more than once",
+                missing,
+                repeated
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Combines two partial solutions to the same problem into one driving the routes of both,
+    /// e.g. after solving clusters of customers separately, as built by
+    /// [`Solution::partial_from_routes`]. Fails if either isn't a feasible tour, if a customer is
+    /// on both, or if the solutions are to problems with different demands, capacities or
+    /// maximum route distances. The merged solution needn't visit every customer, as more
+    /// partial solutions may still be merged in.
+    pub fn merge(self, other: Solution, matrix: &Matrix) -> Result<Solution> {
+        if self.demands != other.demands
+            || self.capacity != other.capacity
+            || self.max_route_distance != other.max_route_distance
+        {
+            bail!("Only solutions to the same problem can be merged");
+        }
+        // Before looking the customers up, as a node that doesn't exist isn't in `served` either
+        validate_tour(&self.tour, &self.demands, self.capacity)?;
+        validate_tour(&other.tour, &other.demands, other.capacity)?;
+
+        let mut served = vec![false; self.demands.len()];
+        for &node in self.routes().flatten() {
+            served[node] = true;
+        }
+        if let Some(&node) = other.routes().flatten().find(|&&node| served[node]) {
+            bail!("Customer {} is served by both solutions", node);
+        }
+
+        let mut tour = vec![0];
+        for route in self.routes().chain(other.routes()) {
+            tour.extend(route);
+            tour.push(0);
+        }
+        let route_costs = route_costs(&tour, matrix);
+        if let Some(max_route_distance) = self.max_route_distance {
+            validate_route_distances(&route_costs, max_route_distance)?;
+        }
+
+        Ok(Solution {
+            cost: tour_cost(&tour, matrix),
+            route_costs,
+            tour,
+            cycles: self.cycles + other.cycles,
+            elapsed: self.elapsed + other.elapsed,
+            edge_usage: None,
+            ..self
+        })
+    }
+
+    /// How far above the known optimum this solution is, as a percentage.
+    pub fn gap(&self) -> Option<f64> {
+        self.optimal_value
+            .map(|optimal| (self.cost - optimal) / optimal * 100.0)
+    }
+
+    /// The customers visited by each vehicle in order, without the depot at either end.
+    pub fn routes(&self) -> impl Iterator<Item = &[usize]> {
+        self.tour
+            .split(|&node| node == 0)
+            .filter(|route| !route.is_empty())
+    }
+
+    /// The same routes as [`Solution::routes`], but each starting and ending at the depot.
+    pub fn routes_with_depot(&self) -> impl Iterator<Item = Vec<usize>> + '_ {
+        self.routes().map(|route| {
+            let mut bookended = Vec::with_capacity(route.len() + 2);
+            bookended.push(0);
+            bookended.extend_from_slice(route);
+            bookended.push(0);
+            bookended
+        })
+    }
+
+    /// How the solution measures up by distance, vehicles, makespan and load variance.
+    pub fn objectives(&self) -> Objectives {
+        Objectives {
+            distance: self.cost,
+            vehicles: self.route_count(),
+            makespan: self.route_costs.iter().copied().fold(0.0, f64::max),
+            load_variance: load_variance(&self.tour, &self.demands),
+        }
+    }
+
+    pub fn route_count(&self) -> usize {
+        self.routes().count()
+    }
+
+    /// The total demand served by route `i`.
+    ///
+    /// Panics if there are not more than `i` routes.
+    pub fn route_load(&self, i: usize) -> f64 {
+        route_load(self.routes().nth(i).unwrap(), &self.demands)
+    }
+
+    /// Compares this solution's routes and cost against `other`'s, e.g. to check whether a change
+    /// to the solver changed what it finds.
+    pub fn diff(&self, other: &Solution) -> SolutionDiff {
+        let same_route = |a: &[usize], b: &[usize]| a == b || a.iter().eq(b.iter().rev());
+
+        let changed_routes = self
+            .routes()
+            .enumerate()
+            .filter(|(_, route)| !other.routes().any(|theirs| same_route(route, theirs)))
+            .map(|(i, _)| i)
+            .collect();
+
+        // Each customer's route-mates as a sorted list, so they compare regardless of order
+        let route_mates = |solution: &Solution| {
+            let mut mates = vec![Vec::new(); solution.demands.len()];
+            for route in solution.routes() {
+                let mut sorted = route.to_vec();
+                sorted.sort_unstable();
+                for &node in route {
+                    mates[node] = sorted.clone();
+                }
+            }
+            mates
+        };
+        let (ours, theirs) = (route_mates(self), route_mates(other));
+        let moved_customers = (1..ours.len().max(theirs.len()))
+            .filter(|&node| ours.get(node) != theirs.get(node))
+            .collect();
+
+        SolutionDiff {
+            cost_delta: other.cost - self.cost,
+            changed_routes,
+            moved_customers,
+        }
+    }
+
+    /// How much more each route's vehicle could carry, e.g. to see where a late order fits.
+    pub fn route_slack(&self) -> Vec<f64> {
+        (0..self.route_count())
+            .map(|i| self.capacity - self.route_load(i))
+            .collect()
+    }
+
+    /// Writes each route as the `x y` coordinates of its stops, one per line, from the depot and
+    /// back again, with a blank line between routes, as gnuplot reads separate lines to plot.
+    /// `coordinates` are the instance's, the depot first.
+    ///
+    /// Panics if a route visits a node without coordinates.
+    pub fn write_plot_data<W: io::Write>(
+        &self,
+        coordinates: &[(f64, f64)],
+        mut w: W,
+    ) -> io::Result<()> {
+        for (i, route) in self.routes_with_depot().enumerate() {
+            if i > 0 {
+                writeln!(w)?;
+            }
+            for node in route {
+                let (x, y) = coordinates[node];
+                writeln!(w, "{} {}", x, y)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Each route on a line listing its customers with their demands and the vehicle's load and
+    /// slack, as in `Route #1: 1(19) 2(30) 21(16) | load 65/100, slack 35`.
+    pub fn route_report(&self) -> String {
+        let mut report = String::new();
+        for (i, route) in self.routes().enumerate() {
+            let _ = write!(report, "Route #{}:", i + 1);
+            for &node in route {
+                let _ = write!(report, " {}({})", node, self.demands[node]);
+            }
+            let load = self.route_load(i);
+            let _ = writeln!(
+                report,
+                " | load {}/{}, slack {}",
+                load,
+                self.capacity,
+                self.capacity - load
+            );
+        }
+        report
+    }
+}
+
+/// The routes and cost in the CVRPLIB `.sol` format [`Solution::from_sol`] reads. The cost has
+/// the formatter's precision, two decimal places by default, e.g. `{:.0}` for whole numbers.
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, route) in self.routes().enumerate() {
+            write!(f, "Route #{}:", i + 1)?;
+            for node in route {
+                write!(f, " {}", node)?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(
+            f,
+            "Cost {}",
+            format_cost(self.cost, f.precision().unwrap_or(2))
+        )
+    }
+}
+
+#[cfg(test)]
+impl Solution {
+    /// A solution driving `tour` with unit demands and a capacity of 10, for tests to adjust.
+    pub(crate) fn for_test(tour: Vec<usize>, cost: f64) -> Solution {
+        Solution {
+            name: String::from("test"),
+            demands: vec![1.0; tour.iter().max().unwrap() + 1],
+            tour,
+            cost,
+            route_costs: Vec::new(),
+            capacity: 10.0,
+            max_route_distance: None,
+            optimal_value: None,
+            seed: None,
+            cycles: 0,
+            elapsed: Duration::ZERO,
+            edge_usage: None,
+            #[cfg(feature = "profiling")]
+            timings: Timings::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aco::matrix::{DistanceMetric, MatrixExt};
+
+    #[test]
+    fn test_from_routes() -> Result<()> {
+        let matrix = Matrix::from_coordinates(
+            &[(0.0, 0.0), (3.0, 4.0), (6.0, 8.0), (0.0, 5.0)],
+            DistanceMetric::Euclidean,
+        );
+        let demands = vec![0.0, 4.0, 4.0, 4.0];
+
+        let solution = Solution::from_routes(
+            vec![vec![1, 2], vec![], vec![3]],
+            &matrix,
+            demands.clone(),
+            8.0,
+        )?;
+        assert_eq!(solution.tour, [0, 1, 2, 0, 3, 0]);
+        assert_eq!(solution.cost, 30.0);
+        assert_eq!(
+            solution.routes().collect::<Vec<_>>(),
+            [&[1, 2][..], &[3][..]]
+        );
+
+        assert!(Solution::from_routes(vec![vec![1, 2, 3]], &matrix, demands.clone(), 8.0).is_err());
+        assert!(Solution::from_routes(vec![vec![4]], &matrix, demands.clone(), 8.0).is_err());
+        assert!(Solution::from_routes(vec![vec![1, 2]], &matrix, demands.clone(), 8.0).is_err());
+        assert!(
+            Solution::from_routes(vec![vec![1, 2], vec![3, 1]], &matrix, demands, 8.0).is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_sol() -> Result<()> {
+        let problem = crate::aco::builder::ProblemBuilder::new()
+            .name("sol")
+            .capacity(8.0)
+            .coordinates(vec![(0.0, 0.0), (3.0, 4.0), (6.0, 8.0), (0.0, 5.0)])
+            .demands(vec![0.0, 4.0, 4.0, 4.0])
+            .build()?;
+
+        let sol = "Route #1: 1 2\nRoute #2: 3\nCost 30\n";
+        let solution = Solution::from_sol(sol.as_bytes(), &problem)?;
+        assert_eq!(solution.name, "sol");
+        assert_eq!(solution.tour, [0, 1, 2, 0, 3, 0]);
+        assert_eq!(solution.cost, 30.0);
+
+        assert!(Solution::from_sol("Route #1: 0 1\n".as_bytes(), &problem).is_err());
+        assert!(Solution::from_sol("Route #1: 1 x\n".as_bytes(), &problem).is_err());
+        assert!(Solution::from_sol("Route #1: 1 2 3\n".as_bytes(), &problem).is_err());
+
+        // Displaying writes the same file back
+        assert_eq!(solution.to_string(), sol);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_tour() -> Result<()> {
+        let problem = crate::aco::builder::ProblemBuilder::new()
+            .name("tour")
+            .capacity(8.0)
+            .coordinates(vec![(0.0, 0.0), (3.0, 4.0), (6.0, 8.0), (0.0, 5.0)])
+            .demands(vec![0.0, 4.0, 4.0, 4.0])
+            .build()?;
+
+        // Starting part way round, with node 5 as a second visit to the depot
+        let tour = "NAME : tour.opt.tour\nTYPE : TOUR\nDIMENSION : 4\nTOUR_SECTION\n\
+            2\n3\n5\n4\n1\n-1\nEOF\n";
+        let solution = Solution::from_tour(tour.as_bytes(), &problem)?;
+        assert_eq!(solution.tour, [0, 3, 0, 1, 2, 0]);
+        assert_eq!(solution.cost, 30.0);
+
+        assert!(Solution::from_tour("2\n3\n".as_bytes(), &problem).is_err());
+        assert!(Solution::from_tour("TOUR_SECTION\n2\n3\n".as_bytes(), &problem).is_err());
+        assert!(
+            Solution::from_tour("TOUR_SECTION\n1\n2\n3\n4\n-1\n".as_bytes(), &problem).is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge() -> Result<()> {
+        let matrix = Matrix::from_coordinates(
+            &[(0.0, 0.0), (3.0, 4.0), (6.0, 8.0), (0.0, 5.0)],
+            DistanceMetric::Euclidean,
+        );
+        let partial = |routes: Vec<Vec<usize>>| {
+            Solution::partial_from_routes(routes, &matrix, vec![0.0, 1.0, 1.0, 1.0], 10.0)
+        };
+
+        let merged = partial(vec![vec![1, 2]])?.merge(partial(vec![vec![3]])?, &matrix)?;
+        assert_eq!(merged.tour, [0, 1, 2, 0, 3, 0]);
+        assert_eq!(merged.cost, 30.0);
+        assert_eq!(merged.route_costs, [20.0, 10.0]);
+        merged.validate()?;
+
+        // Numbered as the routes print them
+        let overlap = partial(vec![vec![1, 2]])?.merge(partial(vec![vec![2]])?, &matrix);
+        let err = overlap.err().unwrap().to_string();
+        assert!(err.contains("Customer 2 is served by both"), "{}", err);
+
+        let missing = Solution {
+            tour: vec![0, 7, 0],
+            ..partial(vec![vec![3]])?
+        };
+        assert!(partial(vec![vec![1]])?.merge(missing, &matrix).is_err());
+
+        let limited = |routes| -> Result<Solution> {
+            Ok(Solution {
+                max_route_distance: Some(15.0),
+                ..partial(routes)?
+            })
+        };
+        let too_far = limited(vec![vec![1, 2]])?.merge(limited(vec![vec![3]])?, &matrix);
+        let err = too_far.err().unwrap().to_string();
+        assert!(err.contains("Route #1 drives 20"), "{}", err);
+        assert!(partial(vec![vec![1]])?
+            .merge(limited(vec![vec![3]])?, &matrix)
+            .is_err());
+
+        assert!(
+            Solution::partial_from_routes(vec![vec![1, 7]], &matrix, vec![1.0; 4], 10.0).is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_objectives() -> Result<()> {
+        let matrix = Matrix::from_coordinates(
+            &[(0.0, 0.0), (3.0, 4.0), (6.0, 8.0), (0.0, 5.0)],
+            DistanceMetric::Euclidean,
+        );
+        let solution = Solution::from_routes(
+            vec![vec![1, 2], vec![3]],
+            &matrix,
+            vec![0.0, 1.0, 1.0, 1.0],
+            2.0,
+        )?;
+
+        let objectives = solution.objectives();
+        assert_eq!(
+            objectives,
+            Objectives {
+                distance: 30.0,
+                vehicles: 2,
+                makespan: 20.0,
+                // Loads of 2 and 1
+                load_variance: 0.25,
+            }
+        );
+        assert_eq!(objectives.weighted(&ObjectiveWeights::default()), 30.0);
+        let weights = ObjectiveWeights {
+            distance: 1.0,
+            vehicles: 100.0,
+            makespan: 0.5,
+            load_variance: 4.0,
+        };
+        assert_eq!(objectives.weighted(&weights), 241.0);
+        assert_eq!(
+            format!("{}", objectives),
+            "distance 30, 2 vehicles, makespan 20, load variance 0.25"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_display_precision() {
+        let solution = Solution::for_test(vec![0, 1, 0], 812.3456789012);
+
+        assert_eq!(solution.to_string(), "Route #1: 1\nCost 812.35\n");
+        assert_eq!(format!("{:.1}", solution), "Route #1: 1\nCost 812.3\n");
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Solution::for_test(vec![0, 1, 2, 0, 3, 0], 0.0)
+            .validate()
+            .is_ok());
+
+        let mut repeated = Solution::for_test(vec![0, 1, 2, 0, 2, 0], 0.0);
+        repeated.demands.push(1.0);
+        let err = repeated.validate().unwrap_err().to_string();
+        assert!(err.contains("[3] never are and [2] are"), "{}", err);
+
+        let too_far = Solution {
+            route_costs: vec![20.0, 40.0],
+            max_route_distance: Some(35.0),
+            ..Solution::for_test(vec![0, 1, 2, 0, 3, 0], 60.0)
+        };
+        let err = too_far.validate().unwrap_err().to_string();
+        assert!(err.contains("Route #2 drives 40"), "{}", err);
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = Solution::for_test(vec![0, 1, 2, 0, 3, 4, 0, 5, 0], 100.0);
+
+        let reordered = Solution::for_test(vec![0, 5, 0, 4, 3, 0, 1, 2, 0], 100.0);
+        assert!(before.diff(&reordered).same_routes());
+
+        let moved = Solution::for_test(vec![0, 1, 2, 3, 0, 4, 0, 5, 0], 90.0);
+        let diff = before.diff(&moved);
+        assert_eq!(diff.cost_delta, -10.0);
+        assert_eq!(diff.changed_routes, vec![0, 1]);
+        assert_eq!(diff.moved_customers, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_write_plot_data() -> Result<()> {
+        let coordinates = [(0.0, 0.0), (3.0, 4.0), (6.0, 8.0), (0.0, 5.5)];
+        let solution = Solution::for_test(vec![0, 1, 2, 0, 3, 0], 0.0);
+
+        let mut plot = Vec::new();
+        solution.write_plot_data(&coordinates, &mut plot)?;
+        assert_eq!(
+            String::from_utf8(plot)?,
+            "0 0\n3 4\n6 8\n0 0\n\n0 0\n0 5.5\n0 0\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_route_report() {
+        let solution = Solution {
+            demands: vec![0.0, 19.0, 30.0, 16.0],
+            capacity: 100.0,
+            ..Solution::for_test(vec![0, 1, 2, 0, 3, 0], 0.0)
+        };
+
+        assert_eq!(
+            solution.route_report(),
+            "Route #1: 1(19) 2(30) | load 49/100, slack 51\n\
+             Route #2: 3(16) | load 16/100, slack 84\n"
+        );
+        assert_eq!(solution.route_slack(), vec![51.0, 84.0]);
+    }
+}
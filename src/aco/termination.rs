@@ -0,0 +1,127 @@
+use std::fmt::Debug;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::time::Duration;
+
+/// Known optima are usually rounded, so a cost this close counts as reaching one.
+const OPTIMUM_EPSILON: f64 = 1e-6;
+
+/// How a run is going, as of the end of its latest cycle.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunContext {
+    /// The number of cycles run so far, including the latest.
+    pub cycle: usize,
+    pub elapsed: Duration,
+    pub best_cost: f64,
+    pub cycles_since_improvement: usize,
+    /// The instance's known optimum, if its comment gives one.
+    pub optimal_value: Option<f64>,
+}
+
+/// Decides when a run should end, checked after every cycle.
+pub trait TerminationCriterion: Debug + Send + Sync {
+    fn should_stop(&self, ctx: &RunContext) -> bool;
+}
+
+/// Stop once this many cycles have run.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MaxCycles(pub usize);
+
+impl TerminationCriterion for MaxCycles {
+    fn should_stop(&self, ctx: &RunContext) -> bool {
+        ctx.cycle >= self.0
+    }
+}
+
+/// Stop once the best tour has gone more than this many cycles without improving.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stagnation(pub usize);
+
+impl TerminationCriterion for Stagnation {
+    fn should_stop(&self, ctx: &RunContext) -> bool {
+        ctx.cycles_since_improvement > self.0
+    }
+}
+
+/// Stop once the run has taken this long, which can only be noticed at the end of a cycle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeBudget(pub Duration);
+
+impl TerminationCriterion for TimeBudget {
+    fn should_stop(&self, ctx: &RunContext) -> bool {
+        ctx.elapsed >= self.0
+    }
+}
+
+/// Stop once the best tour is as good as the known optimum, as there is nothing left to find.
+/// Never stops instances without one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OptimumReached;
+
+impl TerminationCriterion for OptimumReached {
+    fn should_stop(&self, ctx: &RunContext) -> bool {
+        match ctx.optimal_value {
+            Some(optimal) => ctx.best_cost <= optimal + OPTIMUM_EPSILON,
+            None => false,
+        }
+    }
+}
+
+/// Stop once the flag is set, e.g. from a Ctrl-C handler, so that an interrupted run still
+/// returns the best tour found so far.
+#[derive(Clone, Debug, Default)]
+pub struct Interrupted(pub Arc<AtomicBool>);
+
+impl TerminationCriterion for Interrupted {
+    fn should_stop(&self, _ctx: &RunContext) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Stop as soon as any of the criteria would.
+#[derive(Clone, Debug)]
+pub struct Any(pub Vec<Arc<dyn TerminationCriterion>>);
+
+impl TerminationCriterion for Any {
+    fn should_stop(&self, ctx: &RunContext) -> bool {
+        self.0.iter().any(|criterion| criterion.should_stop(ctx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_criteria() {
+        let ctx = RunContext {
+            cycle: 10,
+            elapsed: Duration::from_secs(2),
+            best_cost: 100.0,
+            cycles_since_improvement: 5,
+            optimal_value: Some(90.0),
+        };
+
+        assert!(MaxCycles(10).should_stop(&ctx));
+        assert!(!MaxCycles(11).should_stop(&ctx));
+        assert!(Stagnation(4).should_stop(&ctx));
+        assert!(!Stagnation(5).should_stop(&ctx));
+        assert!(TimeBudget(Duration::from_secs(1)).should_stop(&ctx));
+        assert!(!OptimumReached.should_stop(&ctx));
+        assert!(OptimumReached.should_stop(&RunContext {
+            best_cost: 90.0,
+            ..ctx.clone()
+        }));
+
+        let interrupted = Interrupted::default();
+        assert!(!interrupted.should_stop(&ctx));
+        interrupted.0.store(true, Ordering::Relaxed);
+        assert!(interrupted.should_stop(&ctx));
+
+        assert!(!Any(Vec::new()).should_stop(&ctx));
+        assert!(!Any(vec![Arc::new(MaxCycles(20)), Arc::new(OptimumReached)]).should_stop(&ctx));
+        assert!(Any(vec![Arc::new(MaxCycles(20)), Arc::new(Stagnation(4))]).should_stop(&ctx));
+    }
+}
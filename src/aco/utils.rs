@@ -1,3 +1,6 @@
+use anyhow::{bail, Result};
+use yoos::collections::Matrix;
+
 // May be used in the future, currently unusable because does not duplicate zeroes around path
 #[allow(dead_code)]
 pub fn path_to_slices(path: &mut [usize]) -> Vec<&mut [usize]> {
@@ -20,16 +23,227 @@ pub fn path_to_slices(path: &mut [usize]) -> Vec<&mut [usize]> {
     paths
 }
 
+/// Splits `path` into the customers visited by each vehicle, without the depot. Repeated depot
+/// visits don't produce empty routes, and customers before the first depot visit count as a route
+/// of their own.
 pub fn path_to_routes(path: &[usize]) -> Vec<Vec<usize>> {
-    let mut paths = Vec::new();
-    for &i in path {
-        if i == 0 {
-            paths.push(Vec::new());
-        } else {
-            let last: &mut Vec<usize> = paths.last_mut().unwrap();
-            last.push(i);
+    path.split(|&node| node == 0)
+        .filter(|route| !route.is_empty())
+        .map(|route| route.to_vec())
+        .collect()
+}
+
+/// The routes of `tour` in a form that compares equal for any tour driving the same routes,
+/// whatever order it drives them in and whichever way round: each route starts from its lower
+/// numbered end, and the routes are sorted.
+pub fn canonical_routes(tour: &[usize]) -> Vec<Vec<usize>> {
+    let mut routes = path_to_routes(tour);
+    for route in &mut routes {
+        if route.first() > route.last() {
+            route.reverse();
         }
     }
+    routes.sort_unstable();
+    routes
+}
 
-    paths
+/// The number of vehicles `tour` uses, not counting empty routes.
+pub fn route_count(tour: &[usize]) -> usize {
+    tour.windows(2).filter(|w| w[0] == 0 && w[1] != 0).count()
+}
+
+/// The total demand of the nodes in `route`, which may include the depot.
+pub fn route_load(route: &[usize], demands: &[f64]) -> f64 {
+    route.iter().map(|&node| demands[node]).sum()
+}
+
+/// The total demand served on each of `tour`'s routes, skipping empty routes.
+pub fn route_loads(tour: &[usize], demands: &[f64]) -> Vec<f64> {
+    tour.split(|&node| node == 0)
+        .filter(|route| !route.is_empty())
+        .map(|route| route_load(route, demands))
+        .collect()
+}
+
+/// The population variance of the routes' loads in `tour`, 0 for a tour with no routes.
+pub fn load_variance(tour: &[usize], demands: &[f64]) -> f64 {
+    let loads = route_loads(tour, demands);
+    if loads.is_empty() {
+        return 0.0;
+    }
+    let n = loads.len() as f64;
+    let mean = loads.iter().sum::<f64>() / n;
+    loads.iter().map(|load| (load - mean).powi(2)).sum::<f64>() / n
+}
+
+/// The total distance travelled along `tour`.
+pub fn tour_cost(tour: &[usize], matrix: &Matrix) -> f64 {
+    tour.iter()
+        .zip(tour.iter().skip(1))
+        .fold(0.0, |a, (&i, &j)| a + matrix[i][j])
+}
+
+/// The distance driven on each of `tour`'s routes, depot to depot, skipping empty routes.
+pub fn route_costs(tour: &[usize], matrix: &Matrix) -> Vec<f64> {
+    let mut costs = Vec::new();
+    let mut cost = 0.0;
+    for w in tour.windows(2) {
+        cost += matrix[w[0]][w[1]];
+        if w[1] == 0 {
+            if w[0] != 0 {
+                costs.push(cost);
+            }
+            cost = 0.0;
+        }
+    }
+    costs
+}
+
+/// Checks that `tour` starts and ends at the depot, only visits nodes that exist, and never
+/// carries more than `capacity` between depot visits.
+pub fn validate_tour(tour: &[usize], demands: &[f64], capacity: f64) -> Result<()> {
+    if tour.first() != Some(&0) || tour.last() != Some(&0) {
+        bail!("Tour must start and end at the depot");
+    }
+
+    if let Some(&node) = tour.iter().find(|&&node| node >= demands.len()) {
+        bail!(
+            "Tour visits node {}, but there are only {}",
+            node,
+            demands.len()
+        );
+    }
+
+    for (i, route) in path_to_routes(tour).iter().enumerate() {
+        let load = route_load(route, demands);
+        if load > capacity {
+            bail!(
+                "Route #{} carries {}, exceeding the capacity of {}",
+                i + 1,
+                load,
+                capacity
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that none of `route_costs`, as from [`route_costs`], drives further than
+/// `max_route_distance`.
+pub fn validate_route_distances(route_costs: &[f64], max_route_distance: f64) -> Result<()> {
+    for (i, &distance) in route_costs.iter().enumerate() {
+        if distance > max_route_distance {
+            bail!(
+                "Route #{} drives {}, exceeding the maximum of {}",
+                i + 1,
+                distance,
+                max_route_distance
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// `cost` with `precision` decimal places, or none if it is a whole number, as it is for
+/// instances with integer distances.
+pub fn format_cost(cost: f64, precision: usize) -> String {
+    if cost.fract() == 0.0 {
+        format!("{:.0}", cost)
+    } else {
+        format!("{:.*}", precision, cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aco::matrix::{DistanceMetric, MatrixExt};
+
+    #[test]
+    fn test_path_to_routes() {
+        assert_eq!(
+            path_to_routes(&[0, 1, 2, 0, 3, 0]),
+            vec![vec![1, 2], vec![3]]
+        );
+        assert_eq!(path_to_routes(&[1, 2, 0, 3]), vec![vec![1, 2], vec![3]]);
+        assert_eq!(
+            path_to_routes(&[0, 1, 0, 0, 2, 0, 0]),
+            vec![vec![1], vec![2]]
+        );
+        assert!(path_to_routes(&[0, 0, 0]).is_empty());
+        assert!(path_to_routes(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_canonical_routes() {
+        let routes = canonical_routes(&[0, 1, 2, 0, 5, 4, 3, 0]);
+        assert_eq!(routes, canonical_routes(&[0, 3, 4, 5, 0, 2, 1, 0]));
+        assert_ne!(routes, canonical_routes(&[0, 1, 3, 0, 2, 4, 5, 0]));
+    }
+
+    #[test]
+    fn test_tour_cost() {
+        let matrix = Matrix::from_coordinates(
+            &[(0.0, 0.0), (3.0, 4.0), (6.0, 8.0), (0.0, 5.0)],
+            DistanceMetric::Euclidean,
+        );
+        assert_eq!(
+            tour_cost(&[0, 1, 2, 0, 3, 0], &matrix),
+            5.0 + 5.0 + 10.0 + 5.0 + 5.0
+        );
+        assert_eq!(tour_cost(&[0, 0], &matrix), 0.0);
+        assert_eq!(tour_cost(&[], &matrix), 0.0);
+    }
+
+    #[test]
+    fn test_validate_tour() {
+        let demands = [0.0, 4.0, 4.0, 4.0];
+        assert!(validate_tour(&[0, 1, 2, 0, 3, 0], &demands, 8.0).is_ok());
+        assert!(validate_tour(&[0, 0], &demands, 8.0).is_ok());
+
+        let error = |tour: &[usize], capacity| {
+            validate_tour(tour, &demands, capacity)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error(&[1, 2, 0, 3, 0], 8.0),
+            "Tour must start and end at the depot"
+        );
+        assert_eq!(
+            error(&[0, 1, 2, 0, 3], 8.0),
+            "Tour must start and end at the depot"
+        );
+        assert_eq!(error(&[], 8.0), "Tour must start and end at the depot");
+        assert_eq!(
+            error(&[0, 1, 4, 0], 8.0),
+            "Tour visits node 4, but there are only 4"
+        );
+        assert_eq!(
+            error(&[0, 1, 0, 2, 3, 0], 7.0),
+            "Route #2 carries 8, exceeding the capacity of 7"
+        );
+    }
+
+    #[test]
+    fn test_load_variance() {
+        let demands = [0.0, 2.0, 4.0, 6.0];
+        assert_eq!(
+            route_loads(&[0, 1, 2, 0, 0, 3, 0], &demands),
+            vec![6.0, 6.0]
+        );
+        assert_eq!(load_variance(&[0, 1, 2, 0, 3, 0], &demands), 0.0);
+        // Loads of 2 and 10 around a mean of 6
+        assert_eq!(load_variance(&[0, 1, 0, 2, 3, 0], &demands), 16.0);
+        assert_eq!(load_variance(&[0], &demands), 0.0);
+    }
+
+    #[test]
+    fn test_format_cost() {
+        assert_eq!(format_cost(812.3456789012, 2), "812.35");
+        assert_eq!(format_cost(812.3456789012, 0), "812");
+        assert_eq!(format_cost(784.0, 2), "784");
+    }
 }
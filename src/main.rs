@@ -1,22 +1,696 @@
-use std::fs::File;
-use std::{fmt::Debug, path::PathBuf};
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::{
+    fmt::Debug,
+    path::{Path, PathBuf},
+    sync::{atomic::Ordering, Arc},
+    time::{Duration, Instant},
+};
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{bail, Context, Result};
+use clap::{Args, Parser, Subcommand};
+use rayon::ThreadPoolBuilder;
 
-use aco::{Problem, Simulator};
-
-mod aco;
+use cvrp_aco_rs::aco::{
+    generate_random, read_cached, Any, CostFormat, Interrupted, LocalSearch, Manifest, MatrixExt,
+    Problem, ProblemBuilder, ProblemType, Simulator, SimulatorConfig, Solution, TimeBudget,
+};
+use yoos::collections::Matrix;
 
 #[derive(Parser, Debug)]
 #[clap(about, version, author)]
-struct Args {
+struct Cli {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+// Only ever parsed once, so the size of the largest variant doesn't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Solve instances and print the best tours found
+    Solve(SolveArgs),
+    /// Only parse the instances and report their statistics, without solving them
+    Check(Instances),
+    /// Write a random instance in the TSPLIB format
+    Generate(GenerateArgs),
+    /// Convert an instance to another format, chosen by the output's extension
+    Convert(ConvertArgs),
+}
+
+/// Which instances to work on, either one file or a whole directory.
+#[derive(Args, Debug)]
+struct Instances {
+    /// The instance, or - to read it from standard input
+    #[clap(short, long, required_unless_present = "vrp-dir")]
+    vrp: Option<PathBuf>,
+
+    /// Every .vrp file in this directory
+    #[clap(long, conflicts_with = "vrp")]
+    vrp_dir: Option<PathBuf>,
+}
+
+impl Instances {
+    fn paths(&self) -> Result<Vec<PathBuf>> {
+        match &self.vrp_dir {
+            Some(dir) => vrp_paths(dir),
+            // Clap guarantees one of the two is present
+            None => Ok(vec![self.vrp.clone().unwrap()]),
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+struct SolveArgs {
+    #[clap(flatten)]
+    instances: Instances,
+
+    /// Append a CSV row of results for each solved instance to this file
+    #[clap(long)]
+    csv: Option<PathBuf>,
+
+    /// Write the CSV header line before the rows
+    #[clap(long, requires = "csv")]
+    csv_header: bool,
+
+    /// Number of worker threads the ants build their tours on, 0 meaning one per core
+    #[clap(long, default_value = "0")]
+    threads: usize,
+
+    /// Print each route with the depot at both ends, e.g. `0 1 2 3 0`
+    #[clap(long)]
+    depot_bookends: bool,
+
+    /// Only print one summary line per instance
+    #[clap(long, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Skip the local search ants normally run on their tours
+    #[clap(long)]
+    no_local_search: bool,
+
+    /// Start from the solution in this CVRPLIB .sol file, e.g. one found by another solver
+    #[clap(long, conflicts_with = "vrp-dir")]
+    init_sol: Option<PathBuf>,
+
+    /// Compare against the tour in this TSPLIB .tour file, e.g. the instance's .opt.tour, costed
+    /// with the same distances as ours
+    #[clap(long, conflicts_with = "vrp-dir")]
+    opt_tour: Option<PathBuf>,
+
+    /// Seconds to spend on all the instances together, shared out in proportion to their
+    /// dimensions. Time an instance doesn't use goes to the ones after it
+    #[clap(long)]
+    total_time: Option<f64>,
+
+    /// Write the final pheromone matrix to this file as CSV, one row per node, to see which
+    /// edges the colony converged on
+    #[clap(long, conflicts_with = "vrp-dir")]
+    dump_pheromones: Option<PathBuf>,
+
+    /// Write the x and y of each route's stops to this file, one per line with a blank line
+    /// between routes, to plot with e.g. gnuplot's `plot "file" with lines`
+    #[clap(long, conflicts_with = "vrp-dir")]
+    plot_data: Option<PathBuf>,
+
+    /// Always parse the instances, rather than reusing what was parsed on an earlier run
+    #[clap(long)]
+    no_cache: bool,
+
+    /// Decimal places to print costs with. Whole numbers are printed without any
+    #[clap(long, default_value = "2")]
+    cost_precision: usize,
+
+    /// Unit to label costs with, e.g. km
+    #[clap(long)]
+    cost_unit: Option<String>,
+
+    /// Write the crate version, configuration and results of the run to this file as JSON, to
+    /// publish alongside them
+    #[clap(long)]
+    manifest: Option<PathBuf>,
+
+    /// Print the customers each vehicle serves along with their demands and the vehicle's load
+    /// and spare capacity
+    #[clap(long)]
+    verbose: bool,
+
+    /// Seed the ants' random choices, so that the same seed repeats a run exactly. Each run
+    /// picks its own if not given
+    #[clap(long)]
+    seed: Option<u64>,
+}
+
+#[derive(Args, Debug)]
+struct GenerateArgs {
+    /// Number of customers, not counting the depot
+    #[clap(long)]
+    customers: usize,
+
+    /// How much each vehicle carries
+    #[clap(long, default_value = "100")]
+    capacity: f64,
+
+    /// The same seed always generates the same instance
+    #[clap(long, default_value = "0")]
+    seed: u64,
+
+    /// Where to write the instance, standard output if not given
     #[clap(short, long)]
-    vrp: PathBuf,
+    output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct ConvertArgs {
+    /// The instance to convert, or - to read it from standard input. A .csv file is read as a
+    /// distance matrix, one row per node, and becomes an EXPLICIT TSP instance
+    input: PathBuf,
+
+    /// Where to write it, or - for standard output. A .csv file gets the instance's distance
+    /// matrix, anything else the instance in the TSPLIB format
+    output: PathBuf,
 }
 
 fn main() -> Result<()> {
-    let vrp = File::open(Args::parse().vrp)?;
-    let problem = Problem::try_from_vrp(vrp)?;
-    Simulator::on(problem).run()
+    match Cli::parse().command {
+        Command::Solve(args) => solve(args),
+        Command::Check(instances) => check_files(&instances.paths()?),
+        Command::Generate(args) => generate(args),
+        Command::Convert(args) => convert(args),
+    }
+}
+
+fn solve(args: SolveArgs) -> Result<()> {
+    ThreadPoolBuilder::new()
+        .num_threads(args.threads)
+        .build_global()?;
+
+    let paths = args.instances.paths()?;
+
+    let mut config = SimulatorConfig {
+        depot_bookends: args.depot_bookends,
+        cost_format: CostFormat {
+            precision: args.cost_precision,
+            unit: args.cost_unit.clone(),
+        },
+        quiet: args.quiet,
+        seed: args.seed,
+        ..SimulatorConfig::default()
+    };
+    if args.no_local_search {
+        config.local_search = LocalSearch::None;
+    }
+
+    // Only ever set with the ctrlc feature, by the first Ctrl-C
+    let interrupted = Interrupted::default();
+    #[cfg(feature = "ctrlc")]
+    stop_on_ctrlc(&interrupted)?;
+    config.termination = Arc::new(Any(vec![
+        config.termination.clone(),
+        Arc::new(interrupted.clone()),
+    ]));
+
+    let solutions = solve_files(&paths, &config, &args, &interrupted)?;
+
+    #[cfg(feature = "profiling")]
+    for solution in &solutions {
+        println!("{} {:#?}", solution.name, solution.timings);
+    }
+
+    if args.verbose {
+        for solution in &solutions {
+            println!("{}", solution.name);
+            print!("{}", solution.route_report());
+            println!("{:.*}", config.cost_format.precision, solution.objectives());
+        }
+    }
+
+    if args.quiet {
+        for solution in &solutions {
+            print_result(solution, &config.cost_format);
+        }
+    } else if args.instances.vrp_dir.is_some() {
+        print_summary(&solutions, &config.cost_format);
+    }
+
+    if let Some(csv) = &args.csv {
+        write_csv(csv, &solutions, args.csv_header)?;
+    }
+
+    if let Some(manifest) = &args.manifest {
+        write_manifest(manifest, &paths, &solutions, &config)?;
+    }
+
+    Ok(())
+}
+
+fn generate(args: GenerateArgs) -> Result<()> {
+    let problem = generate_random(args.customers, args.capacity, args.seed)?;
+    match &args.output {
+        Some(path) => problem.write_vrp(File::create(path)?)?,
+        None => problem.write_vrp(io::stdout().lock())?,
+    }
+    Ok(())
+}
+
+/// Reads the input as an instance, or a distance matrix if it is a CSV file, then writes it out
+/// in the format the output's extension asks for.
+fn convert(args: ConvertArgs) -> Result<()> {
+    let csv = |path: &Path| path.extension() == Some(OsStr::new("csv"));
+
+    let problem = if csv(&args.input) {
+        csv_problem(&args.input, &fs::read_to_string(&args.input)?)
+            .with_context(|| format!("Failed to read {}", args.input.display()))?
+    } else {
+        read_problem(&args.input, None)
+            .with_context(|| format!("Failed to read {}", args.input.display()))?
+    };
+
+    let output: Box<dyn Write> = if args.output == Path::new("-") {
+        Box::new(io::stdout().lock())
+    } else {
+        Box::new(File::create(&args.output)?)
+    };
+    if csv(&args.output) {
+        problem.matrix().write_csv(output)?;
+    } else {
+        problem.write_vrp(output)?;
+    }
+    Ok(())
+}
+
+/// The EXPLICIT TSP instance whose distance matrix is the CSV file at `path`, which holds
+/// `contents`, named after the file.
+fn csv_problem(path: &Path, contents: &str) -> Result<Problem> {
+    let name = path
+        .file_stem()
+        .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+    ProblemBuilder::new()
+        .comment(format!("Converted from {}", path.display()))
+        .name(name)
+        .problem_type(ProblemType::Tsp)
+        .matrix(Matrix::try_from_rows(read_csv_rows(contents)?)?)
+        .build()
+}
+
+/// The rows of a CSV file of numbers without a header, as [`MatrixExt::write_csv`] writes them.
+fn read_csv_rows(contents: &str) -> Result<Vec<Vec<f64>>> {
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(i, line)| {
+            line.split(',')
+                .map(|value| {
+                    value
+                        .trim()
+                        .parse()
+                        .with_context(|| format!("Invalid number {:?} on line {}", value, i + 1))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn vrp_paths(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    paths.retain(|path| path.extension() == Some(OsStr::new("vrp")));
+    paths.sort();
+    Ok(paths)
+}
+
+/// Sets `interrupted` on Ctrl-C, which ends the run with the best tour found so far. A second
+/// Ctrl-C exits straight away.
+#[cfg(feature = "ctrlc")]
+fn stop_on_ctrlc(interrupted: &Interrupted) -> Result<()> {
+    let flag = interrupted.0.clone();
+    ctrlc::set_handler(move || {
+        if flag.swap(true, Ordering::Relaxed) {
+            std::process::exit(130);
+        }
+    })?;
+    Ok(())
+}
+
+/// Reads the instance at `path`, through the cache in `cache_dir` if given.
+fn read_problem(path: &Path, cache_dir: Option<&Path>) -> Result<Problem> {
+    match (path == Path::new("-"), cache_dir) {
+        (true, Some(dir)) => read_cached(io::stdin().lock(), dir),
+        (true, None) => Problem::from_reader(io::stdin().lock()),
+        (false, Some(dir)) => read_cached(File::open(path)?, dir),
+        (false, None) => Problem::try_from_vrp(File::open(path)?),
+    }
+}
+
+fn cache_dir() -> PathBuf {
+    std::env::temp_dir().join("cvrp-aco-rs")
+}
+
+/// Solves each instance in turn. With `--total-time`, each gets the share of what is left that
+/// its dimension is of the dimensions left to solve. With `--init-sol`, every instance starts
+/// from the solution in that file. Once `interrupted`, the instances not yet started are skipped.
+fn solve_files(
+    paths: &[PathBuf],
+    config: &SimulatorConfig,
+    args: &SolveArgs,
+    interrupted: &Interrupted,
+) -> Result<Vec<Solution>> {
+    let cache_dir = (!args.no_cache).then(cache_dir);
+    let problems = paths
+        .iter()
+        .map(|path| {
+            #[cfg(feature = "profiling")]
+            let now = Instant::now();
+            let problem = read_problem(path, cache_dir.as_deref())
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            #[cfg(feature = "profiling")]
+            println!("Reading {} took {:?}", path.display(), now.elapsed());
+            Ok(problem)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let total_time = args.total_time.map(Duration::from_secs_f64);
+    let cost_format = &config.cost_format;
+    let start = Instant::now();
+    let mut dimensions_left: usize = problems.iter().map(|problem| problem.dimension).sum();
+    let mut solutions = Vec::with_capacity(paths.len());
+    for (path, problem) in paths.iter().zip(problems) {
+        if interrupted.0.load(Ordering::Relaxed) {
+            break;
+        }
+
+        let mut config = config.clone();
+        if let Some(total_time) = total_time {
+            let time_left = total_time.saturating_sub(start.elapsed());
+            let share = problem.dimension as f64 / dimensions_left.max(1) as f64;
+            config.termination = Arc::new(Any(vec![
+                config.termination.clone(),
+                Arc::new(TimeBudget(time_left.mul_f64(share))),
+            ]));
+        }
+        dimensions_left -= problem.dimension;
+
+        // The simulator only keeps the distances, so hold on to the coordinates to plot
+        let coordinates = match &args.plot_data {
+            Some(_) if problem.coordinates.is_empty() => {
+                bail!("{} has no coordinates to plot", path.display())
+            }
+            Some(_) => Some(problem.coordinates.clone()),
+            None => None,
+        };
+
+        // Read now, as the simulator takes the problem, which may have come from standard input
+        let reference = match &args.opt_tour {
+            Some(opt_tour) => Some(
+                Solution::from_tour(File::open(opt_tour)?, &problem)
+                    .with_context(|| format!("Failed to read {}", opt_tour.display()))?,
+            ),
+            None => None,
+        };
+
+        let mut simulator = match &args.init_sol {
+            Some(sol) => {
+                let initial = Solution::from_sol(File::open(sol)?, &problem)
+                    .with_context(|| format!("Failed to read {}", sol.display()))?;
+                Simulator::warm_start_with_config(problem, config, initial)
+            }
+            None => Simulator::with_config(problem, config),
+        };
+        let solution = simulator
+            .run()
+            .with_context(|| format!("Failed to solve {}", path.display()))?;
+        if let (Some(plot), Some(coordinates)) = (&args.plot_data, &coordinates) {
+            solution.write_plot_data(coordinates, File::create(plot)?)?;
+        }
+        if let Some(dump) = &args.dump_pheromones {
+            simulator.pheromones().write_csv(File::create(dump)?)?;
+        }
+        if let Some(reference) = &reference {
+            compare_opt_tour(reference, &solution, cost_format);
+        }
+        solutions.push(solution);
+    }
+
+    Ok(solutions)
+}
+
+/// Parses each instance and prints its statistics, carrying on past any that fail so every
+/// problem in a batch is reported at once.
+fn check_files(paths: &[PathBuf]) -> Result<()> {
+    let mut failures = 0;
+    for path in paths {
+        match read_problem(path, None) {
+            Ok(problem) => print_stats(&problem),
+            Err(err) => {
+                failures += 1;
+                println!("{}: {}", path.display(), err);
+            }
+        }
+    }
+
+    if failures > 0 {
+        bail!("{} of {} instances failed to parse", failures, paths.len());
+    }
+
+    Ok(())
+}
+
+fn print_stats(problem: &Problem) {
+    print!("{}", problem);
+    if let Some(declared) = problem.declared_vehicles() {
+        if declared < problem.min_vehicles() {
+            println!(
+                "  Infeasible: {} vehicles declared, but at least {} are needed",
+                declared,
+                problem.min_vehicles()
+            );
+        }
+    }
+
+    for (node, &demand) in problem.demands.iter().enumerate() {
+        if demand > problem.capacity {
+            println!(
+                "  Infeasible: node {} has demand {} exceeding the capacity",
+                node + 1,
+                demand
+            );
+        }
+    }
+}
+
+/// One line giving the cost of `solution`, how far it is from the optimum, and how long it took.
+fn print_result(solution: &Solution, cost_format: &CostFormat) {
+    let gap = solution
+        .gap()
+        .map_or_else(String::new, |gap| format!(" (gap {:.2}%)", gap));
+    println!(
+        "{}: cost {}{} in {:.2?}",
+        solution.name,
+        cost_format.format(solution.cost),
+        gap,
+        solution.elapsed
+    );
+}
+
+fn print_summary(solutions: &[Solution], cost_format: &CostFormat) {
+    println!(
+        "{:<20} {:>12} {:>10} {:>12}",
+        "Instance", "Cost", "Gap", "Time"
+    );
+    for solution in solutions {
+        let gap = solution
+            .gap()
+            .map_or_else(|| String::from("-"), |gap| format!("{:.2}%", gap));
+        let time = format!("{:.2?}", solution.elapsed);
+        println!(
+            "{:<20} {:>12} {:>10} {:>12}",
+            solution.name,
+            cost_format.format(solution.cost),
+            gap,
+            time
+        );
+    }
+}
+
+/// Prints the cost of the `reference` tour, e.g. the instance's optimal one, next to the cost of
+/// `solution`. A reference tour cheaper than its instance's recorded optimum means our distances
+/// differ from the ones it was found with, e.g. in how they are rounded.
+fn compare_opt_tour(reference: &Solution, solution: &Solution, cost_format: &CostFormat) {
+    println!(
+        "Reference tour: cost {}, ours {} ({:+.2}%)",
+        cost_format.format(reference.cost),
+        cost_format.format(solution.cost),
+        (solution.cost / reference.cost - 1.0) * 100.0
+    );
+    if let Some(optimal) = reference.optimal_value {
+        if reference.cost + 1e-6 < optimal {
+            println!(
+                "  The reference tour costs less than the recorded optimum of {}, so the \
+                 distances likely differ from the ones it was found with",
+                cost_format.format(optimal)
+            );
+        }
+    }
+}
+
+/// Writes a [`Manifest`] of the runs to `path`. Instances read from standard input can't be read
+/// again to hash them, so they go without.
+fn write_manifest(
+    path: &Path,
+    paths: &[PathBuf],
+    solutions: &[Solution],
+    config: &SimulatorConfig,
+) -> Result<()> {
+    let mut manifest = Manifest::new(config);
+    for (instance, solution) in paths.iter().zip(solutions) {
+        let contents = if instance == Path::new("-") {
+            None
+        } else {
+            Some(fs::read_to_string(instance)?)
+        };
+        manifest.add_run(solution, contents.as_deref());
+    }
+    manifest.write_json(File::create(path)?)
+}
+
+/// Appends one row per solution to the CSV at `path`, creating it if needed.
+fn write_csv(path: &Path, solutions: &[Solution], header: bool) -> Result<()> {
+    let csv = OpenOptions::new().create(true).append(true).open(path)?;
+    write_csv_rows(csv, solutions, header)
+}
+
+fn write_csv_rows<W: Write>(mut csv: W, solutions: &[Solution], header: bool) -> Result<()> {
+    if header {
+        writeln!(
+            csv,
+            "name,dimension,capacity,best_cost,optimum,gap_pct,cycles,elapsed_ms,seed"
+        )?;
+    }
+
+    for solution in solutions {
+        let optimum = solution
+            .optimal_value
+            .map_or_else(String::new, |o| o.to_string());
+        let gap = solution
+            .gap()
+            .map_or_else(String::new, |g| format!("{:.4}", g));
+        let seed = solution.seed.map_or_else(String::new, |s| s.to_string());
+        writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{},{}",
+            csv_field(&solution.name),
+            solution.demands.len(),
+            solution.capacity,
+            solution.cost,
+            optimum,
+            gap,
+            solution.cycles,
+            solution.elapsed.as_millis(),
+            seed,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// `field` quoted if it holds anything that would otherwise split it, with any quotes in it
+/// doubled, as RFC 4180 has it.
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_csv_rows() -> Result<()> {
+        assert_eq!(
+            read_csv_rows("0, 1.5,2\n\n1.5,0,-3\n")?,
+            vec![vec![0.0, 1.5, 2.0], vec![1.5, 0.0, -3.0]]
+        );
+        assert!(read_csv_rows("").unwrap().is_empty());
+
+        let err = read_csv_rows("0,1\n1,x\n").unwrap_err().to_string();
+        assert!(err.contains("\"x\" on line 2"), "{}", err);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_problem_round_trips() -> Result<()> {
+        let csv = "0,3,4\n3,0,5\n4,5,0\n";
+        let problem = csv_problem(Path::new("dir/triangle.csv"), csv)?;
+        assert_eq!(problem.name, "triangle");
+        assert_eq!(problem.comment, "Converted from dir/triangle.csv");
+        assert!(matches!(problem.problem_type, ProblemType::Tsp));
+
+        // Back out as an instance, which parses to the same distances
+        let mut vrp = Vec::new();
+        problem.write_vrp(&mut vrp)?;
+        let reparsed = Problem::from_reader(&vrp[..])?;
+        let mut written = Vec::new();
+        reparsed.matrix().write_csv(&mut written)?;
+        assert_eq!(
+            read_csv_rows(std::str::from_utf8(&written)?)?,
+            read_csv_rows(csv)?
+        );
+
+        // A ragged matrix is an error rather than a panic
+        assert!(csv_problem(Path::new("ragged.csv"), "0,1\n1\n").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("A-n32-k5"), "A-n32-k5");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn test_write_csv_rows() -> Result<()> {
+        let problem = ProblemBuilder::new()
+            .name("commas, quotes \"and all\"")
+            .capacity(10.0)
+            .coordinates(vec![(0.0, 0.0), (3.0, 4.0)])
+            .demands(vec![0.0, 5.0])
+            .build()?;
+        let config = SimulatorConfig {
+            quiet: true,
+            seed: Some(42),
+            ..SimulatorConfig::default()
+        };
+        let solution = Simulator::with_config(problem, config).run()?;
+
+        let mut csv = Vec::new();
+        write_csv_rows(&mut csv, &[solution], true)?;
+        let csv = String::from_utf8(csv)?;
+        let mut lines = csv.lines();
+
+        assert_eq!(
+            lines.next(),
+            Some("name,dimension,capacity,best_cost,optimum,gap_pct,cycles,elapsed_ms,seed")
+        );
+        let row = lines.next().unwrap();
+        assert!(
+            row.starts_with("\"commas, quotes \"\"and all\"\"\",2,10,10,,,"),
+            "{}",
+            row
+        );
+        assert!(row.ends_with(",42"), "{}", row);
+        assert_eq!(lines.next(), None);
+
+        Ok(())
+    }
 }